@@ -0,0 +1,68 @@
+#![warn(clippy::pedantic,clippy::nursery,clippy::unwrap_used,clippy::perf)]
+//!Provides `#[derive(Flag)]` for the `packed_flags` crate.
+//!
+//!You probably want to use this through the `derive` feature of `packed_flags` rather than depending on it directly
+use proc_macro::TokenStream;
+use proc_macro2::Span;
+use quote::quote;
+use syn::{parse_macro_input, Data, DeriveInput, Fields};
+
+/// Derives `packed_flags::FlagIndex` for a fieldless enum
+///
+/// Each variant is assigned an index by the order it is declared in, starting from 0.
+/// The backend used by `FlagSet` is the smallest fixed width flag list which can hold every variant,
+/// falling back to `Blong` when there are more than 128 variants
+#[proc_macro_derive(Flag)]
+pub fn derive_flag(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand(&input).unwrap_or_else(syn::Error::into_compile_error).into()
+}
+
+fn expand(input: &DeriveInput) -> syn::Result<proc_macro2::TokenStream> {
+    let Data::Enum(data) = &input.data else {
+        return Err(syn::Error::new(Span::call_site(), "Flag can only be derived for enums"));
+    };
+    if !input.generics.params.is_empty() {
+        return Err(syn::Error::new_spanned(&input.generics, "Flag cannot be derived for generic enums"));
+    }
+    let mut variants = Vec::with_capacity(data.variants.len());
+    for variant in &data.variants {
+        if !matches!(variant.fields, Fields::Unit) {
+            return Err(syn::Error::new_spanned(variant, "Flag can only be derived for enums without fields"));
+        }
+        variants.push(&variant.ident);
+    }
+    let name = &input.ident;
+    let count = variants.len();
+    let indices = 0..count;
+    let from_indices = 0..count;
+    let names = variants.iter().map(ToString::to_string);
+    let backend = match count {
+        0..=32 => quote!(::packed_flags::B32),
+        33..=64 => quote!(::packed_flags::B64),
+        65..=128 => quote!(::packed_flags::B128),
+        _ => quote!(::packed_flags::Blong),
+    };
+    Ok(quote! {
+        impl ::packed_flags::FlagIndex for #name {
+            type Backend = #backend;
+            const COUNT: usize = #count;
+            fn index(&self) -> usize {
+                match *self {
+                    #(Self::#variants => #indices,)*
+                }
+            }
+            fn from_index(index: usize) -> ::core::option::Option<Self> {
+                match index {
+                    #(#from_indices => ::core::option::Option::Some(Self::#variants),)*
+                    _ => ::core::option::Option::None,
+                }
+            }
+            fn name(&self) -> &'static str {
+                match *self {
+                    #(Self::#variants => #names,)*
+                }
+            }
+        }
+    })
+}
@@ -0,0 +1,68 @@
+use packed_flags::{Blong, Flag, FlagIndex, FlagLs, FlagSet, B128, B32, B64};
+
+#[derive(Flag, Debug, PartialEq, Eq, Clone, Copy)]
+enum Permission {
+    Read,
+    Write,
+    Execute,
+}
+
+#[derive(Flag)]
+#[allow(dead_code)]
+enum Empty {}
+
+#[test]
+fn indices() {
+    assert_eq!(Permission::COUNT, 3);
+    assert_eq!(Permission::Read.index(), 0);
+    assert_eq!(Permission::Execute.index(), 2);
+    assert_eq!(Permission::from_index(1), Some(Permission::Write));
+    assert_eq!(Permission::from_index(3), None);
+    assert_eq!(Permission::Write.name(), "Write");
+    assert_eq!(Empty::COUNT, 0);
+    assert!(Empty::from_index(0).is_none());
+}
+
+#[test]
+fn set_ops() {
+    let mut perms: FlagSet<Permission> = FlagSet::new();
+    assert!(perms.is_empty());
+    assert!(perms.insert(Permission::Write));
+    assert!(!perms.insert(Permission::Write));
+    assert!(perms.insert(Permission::Read));
+    assert!(perms.contains(Permission::Read));
+    assert!(!perms.contains(Permission::Execute));
+    assert_eq!(perms.len(), 2);
+    assert_eq!(perms.iter().collect::<Vec<_>>(), vec![Permission::Read, Permission::Write]);
+    assert!(perms.remove(Permission::Read));
+    assert!(!perms.remove(Permission::Read));
+    assert_eq!(format!("{perms:?}"), "{Write}");
+    assert_eq!(perms.as_flags(), &B32::from_iter(vec![false, true, false]));
+
+    let all: FlagSet<Permission, Blong> = FlagSet::all();
+    assert_eq!(all.len(), 3);
+    let collected: FlagSet<Permission> = [Permission::Execute, Permission::Read].into_iter().collect();
+    assert_eq!(format!("{collected:?}"), "{Read, Execute}");
+}
+
+macro_rules! wide_enum {
+    ($name:ident, $($variant:ident),*) => {
+        #[derive(Flag, Debug, PartialEq)]
+        #[allow(dead_code)]
+        enum $name { $($variant),* }
+    };
+}
+wide_enum!(Wide33, A0, A1, A2, A3, A4, A5, A6, A7, A8, A9, A10, A11, A12, A13, A14, A15, A16, A17, A18, A19, A20, A21, A22, A23, A24, A25, A26, A27, A28, A29, A30, A31, A32);
+
+#[test]
+fn backend_selection() {
+    fn backend_len<E: FlagIndex>() -> usize {
+        <E::Backend as FlagLs>::MAX_LENGTH
+    }
+    assert_eq!(backend_len::<Permission>(), B32::MAX_LENGTH);
+    assert_eq!(backend_len::<Wide33>(), B64::MAX_LENGTH);
+    assert_ne!(backend_len::<Wide33>(), B128::MAX_LENGTH);
+    let mut set: FlagSet<Wide33> = FlagSet::new();
+    set.insert(Wide33::A32);
+    assert_eq!(set.iter().collect::<Vec<_>>(), vec![Wide33::A32]);
+}
@@ -1,11 +1,11 @@
 use std::{ops::{
-    BitAnd, BitAndAssign, BitOr, BitOrAssign, BitXor, BitXorAssign, Index, Not, Shl, ShlAssign,
+    BitAnd, BitAndAssign, BitOr, BitOrAssign, BitXor, BitXorAssign, Index, Not, Range, Shl, ShlAssign,
     Shr, ShrAssign, Sub,
-}, fmt::{UpperHex, LowerHex, Octal, Binary}};
+}, fmt::{Debug, UpperHex, LowerHex, Octal, Binary}};
 
-use crate::{flag_iter, Blong, Bsize, FlagLs, B128, FlagLsError, B64};
+use crate::{flag_debug, flag_iter, flagls::field_width, BAuto, Blong, Bsize, FlagLs, B128, FlagLsError, B64};
 
-#[derive(PartialEq, Eq, Default, Clone, Copy, Debug, Hash)]
+#[derive(PartialEq, Eq, Default, Clone, Copy, Hash)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 /// A list of flags up to 32 flags long, or a 32 bit bitfield
 pub struct B32 {
@@ -13,9 +13,10 @@ pub struct B32 {
     len: usize,
 }
 impl B32 {
-    fn lower_mask(point: usize) -> u32 {
-        if point > 32 {
-            panic!("Cannot create mask more than 32 bits for B32")
+    /// A mask of the bits below `point`, which is every bit once `point` reaches the width of a `u32`
+    const fn lower_mask(point: usize) -> u32 {
+        if point >= u32::BITS as usize {
+            u32::MAX
         } else {
             (1 << point) - 1
         }
@@ -42,12 +43,17 @@ impl B32 {
     pub const fn as_inner(self) -> u32 {
         self.inner
     }
-    fn uper_mask(point: usize) -> u32 {
-        if point > 32 {
-            panic!("Cannot mask above the end of the list");
-        } else {
-            u32::MAX - (1 << point) + 1
-        }
+    /// A mask of the bits at and above `point`, which is no bits once `point` reaches the width of a `u32`
+    const fn uper_mask(point: usize) -> u32 {
+        !Self::lower_mask(point)
+    }
+    /// Shifts `inner` left by `rhs`, where shifting by the whole width or more moves every flag off the end
+    fn shifted_left(inner: u32, rhs: usize) -> u32 {
+        u32::try_from(rhs).ok().and_then(|rhs| inner.checked_shl(rhs)).unwrap_or(0)
+    }
+    /// Shifts `inner` right by `rhs`, where shifting by the whole width or more moves every flag off the end
+    fn shifted_right(inner: u32, rhs: usize) -> u32 {
+        u32::try_from(rhs).ok().and_then(|rhs| inner.checked_shr(rhs)).unwrap_or(0)
     }
     #[must_use]
     pub fn new() -> Self {
@@ -87,7 +93,7 @@ impl FlagLs for B32 {
     fn set_len(&mut self, new_len: usize) {
         assert!(new_len<=Self::MAX_LENGTH,"Cannot set length to a length larger than 32 for B32");
         self.len = new_len;
-        self.inner &= (1 << new_len) - 1;
+        self.inner &= Self::lower_mask(new_len);
     }
 
     fn insert(&mut self, index: usize, flag: bool) {
@@ -131,17 +137,90 @@ impl FlagLs for B32 {
 
     fn set(&mut self, index: usize, flag: bool) {
         if index < self.len() {
-            let uper = self.inner & Self::uper_mask(index + 1);
-            let lower = self.inner & Self::lower_mask(index);
-            self.inner = uper + (u32::from(flag) << index) + lower;
+            self.inner = (self.inner & !(1 << index)) | (u32::from(flag) << index);
         } else {
             panic!("Cannot set out of bounds")
         }
     }
 
-    fn iter(&self) -> flag_iter::Iter<Self> {
+    fn iter(&self) -> flag_iter::Iter<'_, Self> {
         flag_iter::Iter::new(self)
     }
+
+    fn try_get_bits(&self, range: Range<usize>) -> Result<u128, FlagLsError> {
+        let width = field_width(&range, self.len)?;
+        if width == 0 {
+            return Ok(0);
+        }
+        Ok(u128::from((self.inner >> range.start) & Self::lower_mask(width)))
+    }
+
+    fn try_set_bits(&mut self, range: Range<usize>, value: u128) -> Result<(), FlagLsError> {
+        let width = field_width(&range, self.len)?;
+        if width > 0 {
+            let mask = Self::lower_mask(width);
+            let value = u32::try_from(value & u128::from(mask)).expect("Infalible");
+            self.inner = (self.inner & !(mask << range.start)) | (value << range.start);
+        }
+        Ok(())
+    }
+
+    fn and_count(&self, other: &Self) -> usize {
+        usize::try_from((self.inner & other.inner).count_ones()).expect("Infalible")
+    }
+
+    fn or_count(&self, other: &Self) -> usize {
+        usize::try_from((self.inner | other.inner).count_ones()).expect("Infalible")
+    }
+
+    fn xor_count(&self, other: &Self) -> usize {
+        usize::try_from((self.inner ^ other.inner).count_ones()).expect("Infalible")
+    }
+
+    fn swap(&mut self, first: usize, second: usize) {
+        assert!(first < self.len && second < self.len, "Cannot swap flags {first} and {second} of a list of length {}", self.len);
+        if (self.inner >> first) & 1 != (self.inner >> second) & 1 {
+            self.inner ^= (1 << first) | (1 << second);
+        }
+    }
+
+    fn reverse(&mut self) {
+        if self.len > 0 {
+            self.inner = self.inner.reverse_bits() >> (u32::BITS as usize - self.len);
+        }
+    }
+
+    fn retain(&mut self, mut f: impl FnMut(usize, bool) -> bool) {
+        let mut out = 0;
+        let mut kept = 0;
+        for idx in 0..self.len {
+            let flag = (self.inner >> idx) & 1 == 1;
+            if f(idx, flag) {
+                out |= u32::from(flag) << kept;
+                kept += 1;
+            }
+        }
+        self.inner = out;
+        self.len = kept;
+    }
+
+    fn stable_partition(&mut self) -> usize {
+        let count = usize::try_from(self.inner.count_ones()).expect("Infalible");
+        self.inner = Self::lower_mask(count);
+        count
+    }
+
+    fn dedup(&mut self) {
+        if self.len == 0 {
+            return;
+        }
+        // a run starts wherever a flag differs from the one before it
+        let starts = (self.inner ^ (self.inner >> 1)) & Self::lower_mask(self.len).wrapping_shr(1);
+        let runs = 1 + usize::try_from(starts.count_ones()).expect("Infalible");
+        let alternating = if self.inner & 1 == 1 { u32::MAX / 3 } else { (u32::MAX / 3) << 1 };
+        self.inner = alternating & Self::lower_mask(runs);
+        self.len = runs;
+    }
 }
 impl BitAnd<Self> for B32 {
     type Output = Self;
@@ -183,27 +262,27 @@ impl BitXorAssign<Self> for B32 {
 impl Shl<usize> for B32 {
     type Output = Self;
     fn shl(self, rhs: usize) -> Self::Output {
-        Self{inner: self.inner << rhs, len: (self.len + rhs).min(Self::MAX_LENGTH)}
+        Self{inner: Self::shifted_left(self.inner, rhs), len: self.len.saturating_add(rhs).min(Self::MAX_LENGTH)}
     }
 }
 #[allow(clippy::suspicious_op_assign_impl)]
 impl ShlAssign<usize> for B32 {
     fn shl_assign(&mut self, rhs: usize) {
-        self.inner.shl_assign(rhs);
-        self.len = (self.len + rhs).min(Self::MAX_LENGTH);
+        self.inner = Self::shifted_left(self.inner, rhs);
+        self.len = self.len.saturating_add(rhs).min(Self::MAX_LENGTH);
     }
 }
 impl Shr<usize> for B32 {
     type Output = Self;
     fn shr(self, rhs: usize) -> Self::Output {
-        let new_len = if self.len > rhs { self.len - rhs } else { 0 };
-        Self{inner: self.inner >> rhs, len: new_len}
+        let new_len = self.len.saturating_sub(rhs);
+        Self{inner: Self::shifted_right(self.inner, rhs), len: new_len}
     }
 }
 impl ShrAssign<usize> for B32 {
     fn shr_assign(&mut self, rhs: usize) {
-        self.inner.shr_assign(rhs);
-        self.len = if self.len > rhs { self.len - rhs } else { 0 };
+        self.inner = Self::shifted_right(self.inner, rhs);
+        self.len = self.len.saturating_sub(rhs);
     }
 }
 impl Not for B32 {
@@ -241,6 +320,14 @@ impl TryFrom<B128> for B32 {
         }
     }
 }
+#[cfg(any(target_pointer_width = "16", target_pointer_width = "32"))]
+impl From<Bsize> for B32{
+    fn from(value: Bsize) -> Self {
+        let len=value.len();
+        Self { inner: value.as_inner().try_into().expect("Infalible"), len}
+    }
+}
+#[cfg(not(any(target_pointer_width = "16", target_pointer_width = "32")))]
 impl TryFrom<Bsize> for B32{
     type Error = FlagLsError;
     fn try_from(value: Bsize) -> Result<Self, Self::Error> {
@@ -269,6 +356,27 @@ impl TryFrom<Blong> for B32{
         }
     }
 }
+impl TryFrom<BAuto> for B32{
+    type Error = FlagLsError;
+    fn try_from(value: BAuto) -> Result<Self, Self::Error> {
+        value.convert()
+    }
+}
+/// Shows the flags in order as `B32[0110](len=4)`, or with `{:#?}` the length, the number of true flags and the runs of true flags
+/// # Examples
+/// ```
+/// use packed_flags::B32;
+/// use packed_flags::FlagLs;
+///
+/// let flag_ls=B32::from_iter(vec![false,true,true,true,false,true]);
+/// assert_eq!(format!("{flag_ls:?}"),"B32[011101](len=6)");
+/// assert_eq!(format!("{flag_ls:#?}"),"B32 {\n    len: 6,\n    ones: 4,\n    set: [1..=3, 5],\n}");
+/// ```
+impl Debug for B32 {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        flag_debug::fmt_flags(self, "B32", f)
+    }
+}
 impl UpperHex for B32{
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(f,"{:X}",self.inner)
@@ -0,0 +1,451 @@
+use std::{borrow::Cow, fmt::Debug, hash::{Hash, Hasher}, ops::{
+    BitAnd, BitAndAssign, BitOr, BitOrAssign, BitXor, BitXorAssign, Index, Not, Range, Sub, SubAssign,
+}};
+
+use crate::{flag_debug, flag_iter, Blong, Bsize, FlagLs, FlagLsError, B128, B32, B64};
+
+/// A list of flags which picks the smallest backing representation that fits its length
+///
+/// Starts out as a [`B32`], and is promoted to a [`B64`], [`B128`] and finally a [`Blong`] as flags are added.
+/// Useful when the number of flags is only known at runtime
+/// # Examples
+/// ```
+/// use packed_flags::BAuto;
+/// use packed_flags::FlagLs;
+///
+/// let mut flag_ls=BAuto::all_true(32);
+/// assert!(matches!(flag_ls,BAuto::B32(_)));
+/// flag_ls.push(false);
+/// assert!(matches!(flag_ls,BAuto::B64(_)));
+/// assert_eq!(flag_ls.len(),33);
+/// ```
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum BAuto {
+    B32(B32),
+    B64(B64),
+    B128(B128),
+    Blong(Blong),
+}
+impl BAuto {
+    #[must_use]
+    /// Create a new blank empty list of flags
+    pub fn new() -> Self {
+        Self::default()
+    }
+    const fn rank(&self) -> u8 {
+        match self {
+            Self::B32(_) => 0,
+            Self::B64(_) => 1,
+            Self::B128(_) => 2,
+            Self::Blong(_) => 3,
+        }
+    }
+    const fn capacity(&self) -> usize {
+        match self {
+            Self::B32(_) => B32::MAX_LENGTH,
+            Self::B64(_) => B64::MAX_LENGTH,
+            Self::B128(_) => B128::MAX_LENGTH,
+            Self::Blong(_) => Blong::MAX_LENGTH,
+        }
+    }
+    /// Move to the next widest representation
+    fn promote(&mut self) {
+        *self = match std::mem::take(self) {
+            Self::B32(b) => Self::B64(b.into()),
+            Self::B64(b) => Self::B128(b.into()),
+            Self::B128(b) => Self::Blong(b.into()),
+            Self::Blong(b) => Self::Blong(b),
+        };
+    }
+    fn promote_to_rank(&mut self, rank: u8) {
+        while self.rank() < rank {
+            self.promote();
+        }
+    }
+    /// Promote until the list can hold `len` flags
+    fn reserve_len(&mut self, len: usize) {
+        while self.capacity() < len {
+            self.promote();
+        }
+    }
+    /// Promote self so it shares a representation with rhs, and return rhs in that representation
+    fn align<'a>(&mut self, rhs: &'a Self) -> Cow<'a, Self> {
+        let rank = self.rank().max(rhs.rank());
+        self.promote_to_rank(rank);
+        if rhs.rank() == rank {
+            Cow::Borrowed(rhs)
+        } else {
+            let mut out = rhs.clone();
+            out.promote_to_rank(rank);
+            Cow::Owned(out)
+        }
+    }
+    /// The flags of a fixed width representation as words, so they can be compared with a [`Blong`] without allocating
+    fn fixed_words(&self) -> [usize; (u128::BITS / usize::BITS) as usize] {
+        let value = match self {
+            Self::B32(b) => u128::from(b.as_inner()),
+            Self::B64(b) => u128::from(b.as_inner()),
+            Self::B128(b) => b.as_inner(),
+            Self::Blong(_) => unreachable!("Blong is not a fixed width representation"),
+        };
+        let mask = u128::try_from(usize::MAX).expect("Infalible");
+        std::array::from_fn(|idx| usize::try_from((value >> (idx * usize::BITS as usize)) & mask).expect("Infalible"))
+    }
+    /// Counts the ones in `op` applied to the words of both lists, whatever their representations
+    fn count_with(&self, other: &Self, op: fn(usize, usize) -> usize) -> usize {
+        match (self, other) {
+            (Self::Blong(l), Self::Blong(r)) => Blong::count_words(l.inner(), r.inner(), op),
+            (Self::Blong(l), r) => Blong::count_words(l.inner(), &r.fixed_words(), op),
+            (l, Self::Blong(r)) => Blong::count_words(&l.fixed_words(), r.inner(), op),
+            (l, r) => Blong::count_words(&l.fixed_words(), &r.fixed_words(), op),
+        }
+    }
+    /// Moves the flags into the smallest representation which can hold them
+    /// # Examples
+    /// ```
+    /// use packed_flags::BAuto;
+    /// use packed_flags::FlagLs;
+    ///
+    /// let mut flag_ls=BAuto::all_true(200);
+    /// assert!(matches!(flag_ls,BAuto::Blong(_)));
+    /// flag_ls.truncate(40);
+    /// flag_ls.shrink_to_fit();
+    /// assert!(matches!(flag_ls,BAuto::B64(_)));
+    /// assert_eq!(flag_ls,BAuto::all_true(40));
+    /// ```
+    #[allow(clippy::missing_panics_doc)]
+    pub fn shrink_to_fit(&mut self) {
+        let len = self.len();
+        *self = match std::mem::take(self) {
+            Self::B64(b) if len <= B32::MAX_LENGTH => Self::B32(b.try_into().expect("Infalible")),
+            Self::B128(b) if len <= B32::MAX_LENGTH => Self::B32(b.try_into().expect("Infalible")),
+            Self::B128(b) if len <= B64::MAX_LENGTH => Self::B64(b.try_into().expect("Infalible")),
+            Self::Blong(b) if len <= B32::MAX_LENGTH => Self::B32(b.try_into().expect("Infalible")),
+            Self::Blong(b) if len <= B64::MAX_LENGTH => Self::B64(b.try_into().expect("Infalible")),
+            Self::Blong(b) if len <= B128::MAX_LENGTH => Self::B128(b.try_into().expect("Infalible")),
+            other => other,
+        };
+    }
+}
+impl Default for BAuto {
+    fn default() -> Self {
+        Self::B32(B32::default())
+    }
+}
+impl PartialEq for BAuto {
+    /// Two lists are equal when they hold the same flags, regardless of representation
+    fn eq(&self, other: &Self) -> bool {
+        self.len() == other.len() && self.iter().eq(other.iter())
+    }
+}
+impl Eq for BAuto {}
+impl Hash for BAuto {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.len().hash(state);
+        for flag in self.iter() {
+            flag.hash(state);
+        }
+    }
+}
+impl Debug for BAuto {
+    /// Written like the other lists, without the representation currently backing it
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        flag_debug::fmt_flags(self, "BAuto", f)
+    }
+}
+impl Index<usize> for BAuto {
+    type Output = bool;
+    fn index(&self, index: usize) -> &Self::Output {
+        match self {
+            Self::B32(b) => &b[index],
+            Self::B64(b) => &b[index],
+            Self::B128(b) => &b[index],
+            Self::Blong(b) => &b[index],
+        }
+    }
+}
+impl FlagLs for BAuto {
+    const MAX_LENGTH: usize = usize::MAX;
+
+    fn len(&self) -> usize {
+        match self {
+            Self::B32(b) => b.len(),
+            Self::B64(b) => b.len(),
+            Self::B128(b) => b.len(),
+            Self::Blong(b) => b.len(),
+        }
+    }
+
+    fn set_len(&mut self, new_len: usize) {
+        self.reserve_len(new_len);
+        match self {
+            Self::B32(b) => b.set_len(new_len),
+            Self::B64(b) => b.set_len(new_len),
+            Self::B128(b) => b.set_len(new_len),
+            Self::Blong(b) => b.set_len(new_len),
+        }
+    }
+
+    fn try_set_len(&mut self, new_len: usize) -> Result<(), FlagLsError> {
+        self.reserve_len(new_len);
+        match self {
+            Self::B32(b) => b.try_set_len(new_len),
+            Self::B64(b) => b.try_set_len(new_len),
+            Self::B128(b) => b.try_set_len(new_len),
+            Self::Blong(b) => b.try_set_len(new_len),
+        }
+    }
+
+    fn insert(&mut self, index: usize, flag: bool) {
+        assert!(index<=self.len(),"Cannot insert out of bounds");
+        self.reserve_len(self.len() + 1);
+        match self {
+            Self::B32(b) => b.insert(index, flag),
+            Self::B64(b) => b.insert(index, flag),
+            Self::B128(b) => b.insert(index, flag),
+            Self::Blong(b) => b.insert(index, flag),
+        }
+    }
+
+    fn remove(&mut self, index: usize) -> bool {
+        match self {
+            Self::B32(b) => b.remove(index),
+            Self::B64(b) => b.remove(index),
+            Self::B128(b) => b.remove(index),
+            Self::Blong(b) => b.remove(index),
+        }
+    }
+
+    fn clear(&mut self) {
+        match self {
+            Self::B32(b) => b.clear(),
+            Self::B64(b) => b.clear(),
+            Self::B128(b) => b.clear(),
+            Self::Blong(b) => b.clear(),
+        }
+    }
+
+    fn get(&self, index: usize) -> Option<bool> {
+        match self {
+            Self::B32(b) => b.get(index),
+            Self::B64(b) => b.get(index),
+            Self::B128(b) => b.get(index),
+            Self::Blong(b) => b.get(index),
+        }
+    }
+
+    fn set(&mut self, index: usize, flag: bool) {
+        match self {
+            Self::B32(b) => b.set(index, flag),
+            Self::B64(b) => b.set(index, flag),
+            Self::B128(b) => b.set(index, flag),
+            Self::Blong(b) => b.set(index, flag),
+        }
+    }
+
+    fn iter(&self) -> flag_iter::Iter<'_, Self> {
+        flag_iter::Iter::new(self)
+    }
+
+    fn try_get_bits(&self, range: Range<usize>) -> Result<u128, FlagLsError> {
+        match self {
+            Self::B32(b) => b.try_get_bits(range),
+            Self::B64(b) => b.try_get_bits(range),
+            Self::B128(b) => b.try_get_bits(range),
+            Self::Blong(b) => b.try_get_bits(range),
+        }
+    }
+
+    fn try_set_bits(&mut self, range: Range<usize>, value: u128) -> Result<(), FlagLsError> {
+        match self {
+            Self::B32(b) => b.try_set_bits(range, value),
+            Self::B64(b) => b.try_set_bits(range, value),
+            Self::B128(b) => b.try_set_bits(range, value),
+            Self::Blong(b) => b.try_set_bits(range, value),
+        }
+    }
+
+    fn and_count(&self, other: &Self) -> usize {
+        self.count_with(other, |l, r| l & r)
+    }
+
+    fn or_count(&self, other: &Self) -> usize {
+        self.count_with(other, |l, r| l | r)
+    }
+
+    fn xor_count(&self, other: &Self) -> usize {
+        self.count_with(other, |l, r| l ^ r)
+    }
+
+    fn swap(&mut self, first: usize, second: usize) {
+        match self {
+            Self::B32(b) => b.swap(first, second),
+            Self::B64(b) => b.swap(first, second),
+            Self::B128(b) => b.swap(first, second),
+            Self::Blong(b) => b.swap(first, second),
+        }
+    }
+
+    fn reverse(&mut self) {
+        match self {
+            Self::B32(b) => b.reverse(),
+            Self::B64(b) => b.reverse(),
+            Self::B128(b) => b.reverse(),
+            Self::Blong(b) => b.reverse(),
+        }
+    }
+
+    fn retain(&mut self, f: impl FnMut(usize, bool) -> bool) {
+        match self {
+            Self::B32(b) => b.retain(f),
+            Self::B64(b) => b.retain(f),
+            Self::B128(b) => b.retain(f),
+            Self::Blong(b) => b.retain(f),
+        }
+    }
+
+    fn stable_partition(&mut self) -> usize {
+        match self {
+            Self::B32(b) => b.stable_partition(),
+            Self::B64(b) => b.stable_partition(),
+            Self::B128(b) => b.stable_partition(),
+            Self::Blong(b) => b.stable_partition(),
+        }
+    }
+
+    fn dedup(&mut self) {
+        match self {
+            Self::B32(b) => b.dedup(),
+            Self::B64(b) => b.dedup(),
+            Self::B128(b) => b.dedup(),
+            Self::Blong(b) => b.dedup(),
+        }
+    }
+}
+impl BitAndAssign<&Self> for BAuto {
+    fn bitand_assign(&mut self, rhs: &Self) {
+        let rhs = self.align(rhs);
+        match (self, rhs.as_ref()) {
+            (Self::B32(l), Self::B32(r)) => l.bitand_assign(*r),
+            (Self::B64(l), Self::B64(r)) => l.bitand_assign(*r),
+            (Self::B128(l), Self::B128(r)) => l.bitand_assign(*r),
+            (Self::Blong(l), Self::Blong(r)) => l.bitand_assign(r),
+            _ => unreachable!("Operands were aligned to the same representation"),
+        }
+    }
+}
+impl BitAnd<Self> for BAuto {
+    type Output = Self;
+    fn bitand(mut self, rhs: Self) -> Self::Output {
+        self.bitand_assign(&rhs);
+        self
+    }
+}
+impl BitOrAssign<&Self> for BAuto {
+    fn bitor_assign(&mut self, rhs: &Self) {
+        let rhs = self.align(rhs);
+        match (self, rhs.as_ref()) {
+            (Self::B32(l), Self::B32(r)) => l.bitor_assign(*r),
+            (Self::B64(l), Self::B64(r)) => l.bitor_assign(*r),
+            (Self::B128(l), Self::B128(r)) => l.bitor_assign(*r),
+            (Self::Blong(l), Self::Blong(r)) => l.bitor_assign(r),
+            _ => unreachable!("Operands were aligned to the same representation"),
+        }
+    }
+}
+impl BitOr<Self> for BAuto {
+    type Output = Self;
+    fn bitor(mut self, rhs: Self) -> Self::Output {
+        self.bitor_assign(&rhs);
+        self
+    }
+}
+impl BitXorAssign<&Self> for BAuto {
+    fn bitxor_assign(&mut self, rhs: &Self) {
+        let rhs = self.align(rhs);
+        match (self, rhs.as_ref()) {
+            (Self::B32(l), Self::B32(r)) => l.bitxor_assign(*r),
+            (Self::B64(l), Self::B64(r)) => l.bitxor_assign(*r),
+            (Self::B128(l), Self::B128(r)) => l.bitxor_assign(*r),
+            (Self::Blong(l), Self::Blong(r)) => l.bitxor_assign(r),
+            _ => unreachable!("Operands were aligned to the same representation"),
+        }
+    }
+}
+impl BitXor<Self> for BAuto {
+    type Output = Self;
+    fn bitxor(mut self, rhs: Self) -> Self::Output {
+        self.bitxor_assign(&rhs);
+        self
+    }
+}
+///The `-` operation is set difference.
+impl SubAssign<&Self> for BAuto {
+    fn sub_assign(&mut self, rhs: &Self) {
+        let rhs = self.align(rhs);
+        match (self, rhs.as_ref()) {
+            (Self::B32(l), Self::B32(r)) => *l = *l - *r,
+            (Self::B64(l), Self::B64(r)) => *l = *l - *r,
+            (Self::B128(l), Self::B128(r)) => *l = *l - *r,
+            (Self::Blong(l), Self::Blong(r)) => l.sub_assign(r),
+            _ => unreachable!("Operands were aligned to the same representation"),
+        }
+    }
+}
+impl Sub<Self> for BAuto {
+    type Output = Self;
+    fn sub(mut self, rhs: Self) -> Self::Output {
+        self.sub_assign(&rhs);
+        self
+    }
+}
+impl Not for BAuto {
+    type Output = Self;
+    fn not(self) -> Self::Output {
+        match self {
+            Self::B32(b) => Self::B32(!b),
+            Self::B64(b) => Self::B64(!b),
+            Self::B128(b) => Self::B128(!b),
+            Self::Blong(b) => Self::Blong(!b),
+        }
+    }
+}
+impl From<B32> for BAuto {
+    fn from(value: B32) -> Self {
+        Self::B32(value)
+    }
+}
+impl From<B64> for BAuto {
+    fn from(value: B64) -> Self {
+        Self::B64(value)
+    }
+}
+impl From<B128> for BAuto {
+    fn from(value: B128) -> Self {
+        Self::B128(value)
+    }
+}
+/// There is no `Bsize` representation, so this picks the smallest one that fits the length
+impl From<Bsize> for BAuto {
+    fn from(value: Bsize) -> Self {
+        let mut out = Self::B128(value.into());
+        out.shrink_to_fit();
+        out
+    }
+}
+impl From<Blong> for BAuto {
+    fn from(value: Blong) -> Self {
+        Self::Blong(value)
+    }
+}
+impl From<BAuto> for Blong {
+    fn from(value: BAuto) -> Self {
+        match value {
+            BAuto::B32(b) => b.into(),
+            BAuto::B64(b) => b.into(),
+            BAuto::B128(b) => b.into(),
+            BAuto::Blong(b) => b,
+        }
+    }
+}
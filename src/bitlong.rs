@@ -1,10 +1,10 @@
-use std::{ops::{BitAndAssign, BitOrAssign, BitXorAssign, Index, Not, SubAssign}, fmt::{UpperHex, LowerHex, Octal, Binary}};
+use std::{ops::{BitAndAssign, BitOrAssign, BitXorAssign, Index, Not, Range, SubAssign}, fmt::{Debug, UpperHex, LowerHex, Octal, Binary}};
 
-use crate::{flag_iter, Bsize, FlagLs, B128, B32, B64};
+use crate::{flag_debug, flag_iter, flagls::field_width, Bsize, FlagLs, FlagLsError, B128, B32, B64};
 /// An arbitrarily long list of flags
 ///
 /// You should use b32,b64, or b128 instead unless you really need a lot of flags
-#[derive(PartialEq, Eq, Default, Clone, Debug, Hash)]
+#[derive(PartialEq, Eq, Default, Clone, Hash)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Blong {
     inner: Vec<usize>,
@@ -12,16 +12,39 @@ pub struct Blong {
 }
 impl Blong {
     const INNER_SIZE: usize = usize::BITS as usize;
-    fn lower_mask(inner_point: usize) -> usize {
-        if inner_point > Self::INNER_SIZE {
-            panic!("Cannot mask more than pointer size for Blong")
+    /// A mask of the bits below `inner_point`, which is every bit once `inner_point` reaches the width of a `usize`
+    const fn lower_mask(inner_point: usize) -> usize {
+        if inner_point >= usize::BITS as usize {
+            usize::MAX
         } else {
             (1 << inner_point) - 1
         }
     }
-    const fn inner(&self) -> &Vec<usize> {
+    pub(crate) const fn inner(&self) -> &Vec<usize> {
         &self.inner
     }
+    /// Counts the ones in `op` applied to each pair of words, padding the shorter list with zeros
+    ///
+    /// `op` must map a pair of zeros to zero
+    pub(crate) fn count_words(lhs: &[usize], rhs: &[usize], op: fn(usize, usize) -> usize) -> usize {
+        let (long, short) = if lhs.len() >= rhs.len() { (lhs, rhs) } else { (rhs, lhs) };
+        long.iter()
+            .enumerate()
+            .map(|(idx, word)| usize::try_from(op(*word, short.get(idx).copied().unwrap_or(0)).count_ones()).expect("Infalible"))
+            .sum()
+    }
+    /// Applies `op` to each pair of words, padding the shorter list with false, and takes the longer length
+    ///
+    /// `op` must map a pair of zeros to zero, so the flags past the end stay cleared
+    fn combine_words(&mut self, rhs: &Self, op: fn(usize, usize) -> usize) {
+        if rhs.inner.len() > self.inner.len() {
+            self.inner.resize(rhs.inner.len(), 0);
+        }
+        for (idx, word) in self.inner.iter_mut().enumerate() {
+            *word = op(*word, rhs.inner.get(idx).copied().unwrap_or(0));
+        }
+        self.len = self.len.max(rhs.len);
+    }
     /// Converts the bitfield into its integer representation, a `Vec<usize>`, consuming it
     /// 
     /// The first flag is at the least significant bit of the 0th value of the output
@@ -55,12 +78,9 @@ impl Blong {
         out.set_len(len);
         out
     }
-    fn uper_mask(inner_point: usize) -> usize {
-        if inner_point > Self::INNER_SIZE {
-            panic!("Cannot mask above the end of the list")
-        } else {
-            usize::MAX - (1 << inner_point) + 1
-        }
+    /// A mask of the bits at and above `inner_point`, which is no bits once `inner_point` reaches the width of a `usize`
+    const fn uper_mask(inner_point: usize) -> usize {
+        !Self::lower_mask(inner_point)
     }
     #[allow(dead_code)]
     #[must_use]
@@ -71,12 +91,184 @@ impl Blong {
             len: 0,
         }
     }
+    /// Creates an empty list of flags with space for at least `capacity` flags before it needs to reallocate
+    /// # Examples
+    /// ```
+    /// use packed_flags::Blong;
+    /// use packed_flags::FlagLs;
+    ///
+    /// let flag_ls=Blong::with_capacity(1000);
+    /// assert!(flag_ls.is_empty());
+    /// assert!(flag_ls.capacity()>=1000);
+    /// ```
+    #[must_use]
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            inner: Vec::with_capacity(capacity.div_ceil(Self::INNER_SIZE)),
+            len: 0,
+        }
+    }
+    /// The number of flags the list can hold without reallocating
+    #[must_use]
+    pub const fn capacity(&self) -> usize {
+        self.inner.capacity().saturating_mul(Self::INNER_SIZE)
+    }
+    /// The number of extra words needed to hold `additional` more flags than the list currently holds
+    fn additional_words(&self, additional: usize) -> Result<usize, FlagLsError> {
+        let new_len = self.len.checked_add(additional).ok_or(FlagLsError::MaximumLengthExceeded { mx_len: Self::MAX_LENGTH, attempt_len: usize::MAX })?;
+        Ok(new_len.div_ceil(Self::INNER_SIZE).saturating_sub(self.inner.len()))
+    }
+    /// Reserves space for at least `additional` more flags
+    /// # Panics
+    /// Panics if the new length would overflow a `usize`, or if the allocation fails
+    /// # Examples
+    /// ```
+    /// use packed_flags::Blong;
+    /// use packed_flags::FlagLs;
+    ///
+    /// let mut flag_ls=Blong::all_true(10);
+    /// flag_ls.reserve(500);
+    /// assert!(flag_ls.capacity()>=510);
+    /// assert_eq!(flag_ls,Blong::all_true(10));
+    /// ```
+    pub fn reserve(&mut self, additional: usize) {
+        let words = self.additional_words(additional).expect("Cannot reserve more than the maximum length of a Blong");
+        self.inner.reserve(words);
+    }
+    /// Attempts to reserve space for at least `additional` more flags
+    /// # Errors
+    /// Errors with [`FlagLsError::MaximumLengthExceeded`] if the new length would overflow a `usize`,
+    /// or [`FlagLsError::AllocationFailed`] if the memory could not be allocated. The list is unchanged on error
+    /// # Examples
+    /// ```
+    /// use packed_flags::{Blong, FlagLsError};
+    /// use packed_flags::FlagLs;
+    ///
+    /// let mut flag_ls=Blong::all_true(10);
+    /// assert_eq!(flag_ls.try_reserve(100),Ok(()));
+    /// assert_eq!(flag_ls.try_reserve(usize::MAX/2),Err(FlagLsError::AllocationFailed { attempt_len: usize::MAX/2+10 }));
+    /// assert_eq!(flag_ls,Blong::all_true(10));
+    /// ```
+    pub fn try_reserve(&mut self, additional: usize) -> Result<(), FlagLsError> {
+        let words = self.additional_words(additional)?;
+        self.inner.try_reserve(words).map_err(|_| FlagLsError::AllocationFailed { attempt_len: self.len + additional })
+    }
+    /// Shrinks the allocation to fit the current length as closely as possible
+    /// # Examples
+    /// ```
+    /// use packed_flags::Blong;
+    /// use packed_flags::FlagLs;
+    ///
+    /// let mut flag_ls=Blong::with_capacity(1000);
+    /// flag_ls.set_len(10);
+    /// flag_ls.shrink_to_fit();
+    /// assert!(flag_ls.capacity()<1000);
+    /// ```
+    pub fn shrink_to_fit(&mut self) {
+        self.inner.shrink_to_fit();
+    }
+    /// Sets every flag in `range` to `value`, a word at a time
+    fn fill(&mut self, range: Range<usize>, value: bool) {
+        let mut idx = range.start;
+        while idx < range.end {
+            let (t_index, m_index) = (idx / Self::INNER_SIZE, idx % Self::INNER_SIZE);
+            let width = (Self::INNER_SIZE - m_index).min(range.end - idx);
+            let mask = Self::lower_mask(width) << m_index;
+            if value {
+                self.inner[t_index] |= mask;
+            } else {
+                self.inner[t_index] &= !mask;
+            }
+            idx += width;
+        }
+    }
+    /// The word made of the `usize::BITS` flags starting `shift` bits below the start of word `t_index`, reading from `words`
+    ///
+    /// Words outside `words`, and any word below `floor`, read as zero
+    fn word_at(words: &[usize], floor: usize, t_index: usize, shift: usize) -> usize {
+        let word = |idx: usize| if idx < floor { 0 } else { words.get(idx).copied().unwrap_or(0) };
+        if shift == 0 {
+            word(t_index)
+        } else {
+            (word(t_index) << shift) | t_index.checked_sub(1).map_or(0, |below| word(below) >> (Self::INNER_SIZE - shift))
+        }
+    }
+    /// Inserts `count` copies of `value` starting at `index`, moving the flags after it up a whole word at a time
+    /// # Panics
+    /// Panics if `index>self.len()` or the new length would overflow a `usize`
+    /// # Examples
+    /// ```
+    /// use packed_flags::Blong;
+    /// use packed_flags::FlagLs;
+    ///
+    /// let mut flag_ls=Blong::all_false(100);
+    /// flag_ls.insert_many(64,70,true);
+    /// assert_eq!(flag_ls.len(),170);
+    /// assert!(flag_ls.iter().enumerate().all(|(idx,flag)| flag==(64..134).contains(&idx)));
+    /// ```
+    pub fn insert_many(&mut self, index: usize, count: usize, value: bool) {
+        assert!(index <= self.len, "Cannot insert at {index} in a list of length {}", self.len);
+        let new_len = self.len.checked_add(count).expect("Cannot insert more than the maximum length of a Blong");
+        if count == 0 {
+            return;
+        }
+        let (t_index, m_index) = (index / Self::INNER_SIZE, index % Self::INNER_SIZE);
+        let (word_shift, bit_shift) = (count / Self::INNER_SIZE, count % Self::INNER_SIZE);
+        self.inner.resize(new_len.div_ceil(Self::INNER_SIZE), 0);
+        let low = self.inner[t_index] & Self::lower_mask(m_index);
+        self.inner[t_index] &= Self::uper_mask(m_index);
+        // a single carry pass from the top down, so every word is read before it is overwritten
+        for dst in ((index + count) / Self::INNER_SIZE..self.inner.len()).rev() {
+            self.inner[dst] = dst.checked_sub(word_shift).map_or(0, |src| Self::word_at(&self.inner, t_index, src, bit_shift));
+        }
+        self.inner[t_index] = (self.inner[t_index] & Self::uper_mask(m_index)) | low;
+        self.len = new_len;
+        self.fill(index..index + count, value);
+    }
+    /// Removes the flags in `range`, moving the flags after it down a whole word at a time
+    ///
+    /// Unlike [`drain`][FlagLs::drain] the removed flags are dropped rather than returned
+    /// # Panics
+    /// Panics if the range starts after it ends or ends after the end of the list
+    /// # Examples
+    /// ```
+    /// use packed_flags::Blong;
+    /// use packed_flags::FlagLs;
+    ///
+    /// let mut flag_ls=Blong::from_iter((0..200).map(|idx| idx%2==0));
+    /// flag_ls.remove_range(1..131);
+    /// assert_eq!(flag_ls,Blong::from_iter((0..70).map(|idx| idx%2==0)));
+    /// ```
+    pub fn remove_range(&mut self, range: Range<usize>) {
+        let len = self.len;
+        assert!(range.start <= range.end && range.end <= len, "Cannot remove {range:?} from a list of length {len}");
+        let count = range.len();
+        if count == 0 {
+            return;
+        }
+        let (t_index, m_index) = (range.start / Self::INNER_SIZE, range.start % Self::INNER_SIZE);
+        let (word_shift, bit_shift) = (count / Self::INNER_SIZE, count % Self::INNER_SIZE);
+        let low = self.inner[t_index] & Self::lower_mask(m_index);
+        let new_words = (len - count).div_ceil(Self::INNER_SIZE);
+        // a single carry pass from the bottom up, so every word is read before it is overwritten
+        for dst in t_index..new_words {
+            let src = dst + word_shift;
+            let lower = self.inner.get(src).copied().unwrap_or(0);
+            let upper = self.inner.get(src + 1).copied().unwrap_or(0);
+            self.inner[dst] = if bit_shift == 0 { lower } else { (lower >> bit_shift) | (upper << (Self::INNER_SIZE - bit_shift)) };
+        }
+        self.inner.truncate(new_words);
+        if let Some(word) = self.inner.get_mut(t_index) {
+            *word = (*word & Self::uper_mask(m_index)) | low;
+        }
+        self.len -= count;
+    }
 }
 impl Index<usize> for Blong {
     type Output = bool;
     fn index(&self, index: usize) -> &Self::Output {
-        if index > self.len {
-            panic!("Index out of bounds")
+        if index >= self.len {
+            panic!("Cannot get element {index} of flag list of length {}", self.len)
         } else {
             let (t_index, m_index) = (index / Self::INNER_SIZE, index % Self::INNER_SIZE);
             if (self.inner[t_index] >> m_index) & 1 == 1 {
@@ -95,75 +287,56 @@ impl FlagLs for Blong {
     }
 
     fn set_len(&mut self, new_len: usize) {
-        let (t_len,m_len) = ((new_len+Self::INNER_SIZE-1) / Self::INNER_SIZE,((new_len+Self::INNER_SIZE-1)%Self::INNER_SIZE)+1);
-        println!("t: {t_len}, m: {m_len}");
+        // m_len is the number of flags used in the last word, 0 meaning it is full
+        let (t_len,m_len) = (new_len.div_ceil(Self::INNER_SIZE),new_len%Self::INNER_SIZE);
         match t_len.cmp(&self.inner.len()) {
             std::cmp::Ordering::Greater => {
-                let mut new: Vec<usize> = vec![0; t_len - self.inner.len()];
-                self.inner.append(&mut new);
+                self.inner.resize(t_len, 0);
             }
             std::cmp::Ordering::Less => {
                 let _ = self.inner.drain(t_len..);
             }
             std::cmp::Ordering::Equal => {}
         }
-        if t_len>0{
-            self.inner[t_len-1] &= (1 << m_len) - 1;
+        if m_len>0 {
+            self.inner[t_len-1] &= Self::lower_mask(m_len);
         }
         self.len = new_len;
     }
 
-    fn insert(&mut self, index: usize, flag: bool) {
-        if index > self.len {
-            panic!("Index out of bounds");
-        } else {
-            let mut t_index = index / Self::INNER_SIZE;
-            let m_index = index % Self::INNER_SIZE;
-            if t_index == self.len {
-                self.inner.push(usize::from(flag));
-            } else {
-                let lower = self.inner[t_index] & Self::lower_mask(m_index);
-                let upper = self.inner[t_index] & Self::uper_mask(m_index);
-                let mut top = self.inner[t_index] >> (Self::INNER_SIZE - 1);
-
-                self.inner[t_index] = (upper << 1) + ((usize::from(flag)) << m_index) + lower;
-                t_index += 1;
-                while t_index < self.inner.len() {
-                    let old = top;
-                    top = self.inner[t_index] >> (Self::INNER_SIZE - 1);
-                    self.inner[t_index] = (self.inner[t_index] << 1) + old;
-                    t_index += 1;
-                }
-                if m_index == Self::INNER_SIZE - 1 {
-                    self.inner.push(top);
-                }
-            }
-            self.len += 1;
+    /// Attempts to set the length of the list, filling any new flags with false
+    ///
+    /// Unlike [`set_len`][FlagLs::set_len], running out of memory is reported rather than aborting
+    /// # Errors
+    /// Errors with [`FlagLsError::AllocationFailed`] if the memory for the new flags could not be allocated. The list is unchanged on error
+    /// # Examples
+    /// ```
+    /// use packed_flags::{Blong, FlagLsError};
+    /// use packed_flags::FlagLs;
+    ///
+    /// let mut flag_ls=Blong::default();
+    /// assert_eq!(flag_ls.try_set_len(100),Ok(()));
+    /// assert_eq!(flag_ls,Blong::all_false(100));
+    /// assert!(matches!(flag_ls.try_set_len(usize::MAX),Err(FlagLsError::AllocationFailed { .. })));
+    /// assert_eq!(flag_ls.len(),100);
+    /// ```
+    fn try_set_len(&mut self, new_len: usize) -> Result<(), FlagLsError> {
+        if new_len > self.len {
+            self.try_reserve(new_len - self.len)?;
         }
+        self.set_len(new_len);
+        Ok(())
     }
 
-    fn remove(&mut self, index: usize) -> bool {
-        if index >= self.len {
-            panic!("Index out of bounds")
-        } else {
-            let t_index = index / Self::INNER_SIZE;
-            let m_indx = index % Self::INNER_SIZE;
-            let mut top_idx = self.inner.len() - 1;
-            let mut bot: usize = 0;
+    fn insert(&mut self, index: usize, flag: bool) {
+        self.insert_many(index, 1, flag);
+    }
 
-            while top_idx > t_index {
-                let old = bot;
-                bot = self.inner[top_idx] & 1;
-                self.inner[top_idx] = (self.inner[top_idx] >> 1) + (old << (Self::INNER_SIZE - 1));
-                top_idx -= 1;
-            }
-            let upper = self.inner[t_index] & Self::uper_mask(m_indx + 1);
-            let lower = self.inner[t_index] & Self::lower_mask(m_indx);
-            let out = (self.inner[t_index] >> m_indx) & 1;
-            self.inner[t_index] = lower + (upper >> 1) + (bot << (Self::INNER_SIZE - 1));
-            self.len -= 1;
-            out == 1
-        }
+    fn remove(&mut self, index: usize) -> bool {
+        let out = self.get(index);
+        assert!(out.is_some(), "Cannot remove flag {index} of a list of length {}", self.len);
+        self.remove_range(index..index + 1);
+        out == Some(true)
     }
 
     fn clear(&mut self) {
@@ -183,52 +356,155 @@ impl FlagLs for Blong {
     fn set(&mut self, index: usize, flag: bool) {
         if index < self.len {
             let (t_index, m_index) = (index / Self::INNER_SIZE, index % Self::INNER_SIZE);
-            let upper = self.inner[t_index] & Self::uper_mask(m_index + 1);
-            let lower = self.inner[t_index] & Self::lower_mask(m_index);
-            self.inner[t_index] = upper + ((usize::from(flag)) << m_index) + lower;
+            self.inner[t_index] = (self.inner[t_index] & !(1 << m_index)) | (usize::from(flag) << m_index);
         } else {
             panic!("Cannot set out of bounds")
         }
     }
 
-    fn iter(&self) -> flag_iter::Iter<Self> {
+    fn iter(&self) -> flag_iter::Iter<'_, Self> {
         flag_iter::Iter::new(self)
     }
-}
-impl BitAndAssign<&Self> for Blong {
-    fn bitand_assign(&mut self, rhs: &Self) {
-        for i in 0..self.inner.len().min(rhs.inner().len()) {
-            self.inner[i].bitand_assign(rhs.inner()[i]);
+
+    fn try_get_bits(&self, range: Range<usize>) -> Result<u128, FlagLsError> {
+        field_width(&range, self.len)?;
+        let mut out = 0;
+        let mut idx = range.start;
+        // read the field a word at a time, as it may cross several words
+        while idx < range.end {
+            let (t_index, m_index) = (idx / Self::INNER_SIZE, idx % Self::INNER_SIZE);
+            let width = (Self::INNER_SIZE - m_index).min(range.end - idx);
+            let part = (self.inner[t_index] >> m_index) & Self::lower_mask(width);
+            out |= u128::try_from(part).expect("Infalible") << (idx - range.start);
+            idx += width;
+        }
+        Ok(out)
+    }
+
+    fn try_set_bits(&mut self, range: Range<usize>, value: u128) -> Result<(), FlagLsError> {
+        field_width(&range, self.len)?;
+        let mut idx = range.start;
+        while idx < range.end {
+            let (t_index, m_index) = (idx / Self::INNER_SIZE, idx % Self::INNER_SIZE);
+            let width = (Self::INNER_SIZE - m_index).min(range.end - idx);
+            let mask = Self::lower_mask(width);
+            let part = usize::try_from((value >> (idx - range.start)) & u128::try_from(mask).expect("Infalible")).expect("Infalible");
+            self.inner[t_index] = (self.inner[t_index] & !(mask << m_index)) | (part << m_index);
+            idx += width;
+        }
+        Ok(())
+    }
+
+    fn and_count(&self, other: &Self) -> usize {
+        Self::count_words(&self.inner, &other.inner, |l, r| l & r)
+    }
+
+    fn or_count(&self, other: &Self) -> usize {
+        Self::count_words(&self.inner, &other.inner, |l, r| l | r)
+    }
+
+    fn xor_count(&self, other: &Self) -> usize {
+        Self::count_words(&self.inner, &other.inner, |l, r| l ^ r)
+    }
+
+    fn swap(&mut self, first: usize, second: usize) {
+        assert!(first < self.len && second < self.len, "Cannot swap flags {first} and {second} of a list of length {}", self.len);
+        let (first_word, first_bit) = (first / Self::INNER_SIZE, first % Self::INNER_SIZE);
+        let (second_word, second_bit) = (second / Self::INNER_SIZE, second % Self::INNER_SIZE);
+        if (self.inner[first_word] >> first_bit) & 1 != (self.inner[second_word] >> second_bit) & 1 {
+            self.inner[first_word] ^= 1 << first_bit;
+            self.inner[second_word] ^= 1 << second_bit;
         }
-        for i in self.inner.len().min(rhs.inner().len())..self.inner.len() {
-            self.inner[i] = 0;
+    }
+
+    fn reverse(&mut self) {
+        self.inner.reverse();
+        for word in &mut self.inner {
+            *word = word.reverse_bits();
+        }
+        // the padding past the end of the list is now at the start, so shift it out
+        let shift = self.inner.len() * Self::INNER_SIZE - self.len;
+        if shift > 0 {
+            for idx in 0..self.inner.len() {
+                let next = self.inner.get(idx + 1).copied().unwrap_or(0);
+                self.inner[idx] = (self.inner[idx] >> shift) | (next << (Self::INNER_SIZE - shift));
+            }
+        }
+    }
+
+    fn retain(&mut self, mut f: impl FnMut(usize, bool) -> bool) {
+        let mut kept = 0;
+        let mut word = 0;
+        for idx in 0..self.len {
+            let flag = (self.inner[idx / Self::INNER_SIZE] >> (idx % Self::INNER_SIZE)) & 1 == 1;
+            if f(idx, flag) {
+                word |= usize::from(flag) << (kept % Self::INNER_SIZE);
+                kept += 1;
+                // kept flags never overtake the flag being read, so a finished word can be written in place
+                if kept % Self::INNER_SIZE == 0 {
+                    self.inner[kept / Self::INNER_SIZE - 1] = word;
+                    word = 0;
+                }
+            }
+        }
+        if kept % Self::INNER_SIZE != 0 {
+            self.inner[kept / Self::INNER_SIZE] = word;
+        }
+        self.inner.truncate(kept.div_ceil(Self::INNER_SIZE));
+        self.len = kept;
+    }
+
+    fn stable_partition(&mut self) -> usize {
+        let count = self.and_count(self);
+        let (full, rem) = (count / Self::INNER_SIZE, count % Self::INNER_SIZE);
+        for (idx, word) in self.inner.iter_mut().enumerate() {
+            *word = match idx.cmp(&full) {
+                std::cmp::Ordering::Less => usize::MAX,
+                std::cmp::Ordering::Equal if rem > 0 => Self::lower_mask(rem),
+                _ => 0,
+            };
+        }
+        count
+    }
+
+    fn dedup(&mut self) {
+        if self.len == 0 {
+            return;
         }
-        for _i in self.inner.len().min(rhs.inner().len())..rhs.inner().len() {
-            self.inner.push(0);
+        // a run starts wherever a flag differs from the one before it
+        let mut runs = 1;
+        for idx in 0..self.inner.len() {
+            let next = self.inner.get(idx + 1).copied().unwrap_or(0);
+            let mut starts = self.inner[idx] ^ ((self.inner[idx] >> 1) | (next << (Self::INNER_SIZE - 1)));
+            let compared = (self.len - 1).saturating_sub(idx * Self::INNER_SIZE);
+            if compared < Self::INNER_SIZE {
+                starts &= (1 << compared) - 1;
+            }
+            runs += usize::try_from(starts.count_ones()).expect("Infalible");
+        }
+        let alternating = if self.inner[0] & 1 == 1 { usize::MAX / 3 } else { (usize::MAX / 3) << 1 };
+        self.inner.truncate(runs.div_ceil(Self::INNER_SIZE));
+        self.inner.fill(alternating);
+        let rem = runs % Self::INNER_SIZE;
+        if let (Some(last), true) = (self.inner.last_mut(), rem > 0) {
+            *last &= Self::lower_mask(rem);
         }
-        self.len = self.len.max(rhs.len());
+        self.len = runs;
+    }
+}
+impl BitAndAssign<&Self> for Blong {
+    fn bitand_assign(&mut self, rhs: &Self) {
+        self.combine_words(rhs, |l, r| l & r);
     }
 }
 impl BitOrAssign<&Self> for Blong {
     fn bitor_assign(&mut self, rhs: &Self) {
-        for i in 0..self.inner.len().min(rhs.inner().len()) {
-            self.inner[i].bitor_assign(rhs.inner()[i]);
-        }
-        for i in self.inner.len().min(rhs.inner().len())..rhs.inner().len() {
-            self.inner.push(rhs.inner()[i]);
-        }
-        self.len = self.len.max(rhs.len());
+        self.combine_words(rhs, |l, r| l | r);
     }
 }
 impl BitXorAssign<&Self> for Blong {
     fn bitxor_assign(&mut self, rhs: &Self) {
-        for i in 0..self.inner.len().min(rhs.inner().len()) {
-            self.inner[i].bitand_assign(rhs.inner()[i]);
-        }
-        for _i in self.inner.len().min(rhs.inner().len())..rhs.inner().len() {
-            self.inner.push(rhs.inner()[1]);
-        }
-        self.len = self.len.max(rhs.len());
+        self.combine_words(rhs, |l, r| l ^ r);
     }
 }
 impl Not for Blong {
@@ -238,15 +514,18 @@ impl Not for Blong {
         for i in 0..len {
             self.inner[i] = !self.inner[i];
         }
-        self.inner[len - 1] &= Self::lower_mask(self.len % Self::INNER_SIZE);
+        let rem = self.len % Self::INNER_SIZE;
+        if let (Some(last), true) = (self.inner.last_mut(), rem > 0) {
+            *last &= Self::lower_mask(rem);
+        }
         self
     }
 }
 impl SubAssign<&Self> for Blong {
-    /// Subtration is set difference
+    /// Subtration is set difference, so the length is unchanged
     fn sub_assign(&mut self, rhs: &Self) {
-        for i in 0..self.inner.len() {
-            self.inner[i] &= !rhs.inner()[i];
+        for (word, other) in self.inner.iter_mut().zip(&rhs.inner) {
+            *word &= !other;
         }
     }
 }
@@ -268,7 +547,7 @@ impl From<B32> for Blong{
                 }
                 inner
             }, |r| vec![r]);
-        Self { inner, len}
+        Self::initialize(inner, len)
     }
 }
 #[allow(clippy::fallible_impl_from)]
@@ -289,7 +568,7 @@ impl From<B64> for Blong{
                 }
                 inner
             }, |r| vec![r]);
-        Self { inner, len}
+        Self::initialize(inner, len)
     }
 }
 #[allow(clippy::fallible_impl_from)]
@@ -310,49 +589,63 @@ impl From<B128> for Blong{
                 }
                 inner
             }, |r| vec![r]);
-        Self { inner, len}
+        Self::initialize(inner, len)
     }
 }
 impl From<Bsize> for Blong{
     fn from(value: Bsize) -> Self {
         let len=value.len();
-        Self { inner: vec![value.as_inner()], len}
+        Self::initialize(vec![value.as_inner()], len)
+    }
+}
+/// Shows the flags in order as `Blong[0110](len=4)`, or with `{:#?}` the length, the number of true flags and the runs of true flags
+///
+/// Only the first and last 64 flags of lists longer than 128 are shown, with a count of the flags between them
+/// # Examples
+/// ```
+/// use packed_flags::Blong;
+/// use packed_flags::FlagLs;
+///
+/// let mut flag_ls=Blong::all_false(1000);
+/// flag_ls.set(0,true);
+/// flag_ls.set(500,true);
+/// assert_eq!(format!("{flag_ls:?}"),format!("Blong[1{}…(872 more, 1 set)…{}](len=1000)","0".repeat(63),"0".repeat(64)));
+/// ```
+impl Debug for Blong {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        flag_debug::fmt_flags(self, "Blong", f)
     }
 }
 impl UpperHex for Blong{
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let mut out= String::new();
         for t in &self.inner{
-            out+=&format!("{:X}",*t);
+            write!(f,"{:X}",*t)?;
         }
-        write!(f,"{out}")
+        Ok(())
     }
 }
 impl LowerHex for Blong{
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let mut out= String::new();
         for t in &self.inner{
-            out+=&format!("{:x}",*t);
+            write!(f,"{:x}",*t)?;
         }
-        write!(f,"{out}")
+        Ok(())
     }
 }
 impl Octal for Blong{
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let mut out= String::new();
         for t in &self.inner{
-            out+=&format!("{:o}",*t);
+            write!(f,"{:o}",*t)?;
         }
-        write!(f,"{out}")
+        Ok(())
     }
 }
 /// Probably a bad idea to ever use this
 impl Binary for Blong{
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let mut out= String::new();
         for t in &self.inner{
-            out+=&format!("{:b}",*t);
+            write!(f,"{:b}",*t)?;
         }
-        write!(f,"{out}")
+        Ok(())
     }
 }
\ No newline at end of file
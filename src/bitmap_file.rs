@@ -0,0 +1,403 @@
+use std::{
+    error::Error,
+    fmt::Display,
+    io::{self, Read, Write},
+};
+
+use crate::{flagls::fill, BitOrder, FlagLs, FlagLsError, FlagView};
+
+const MAGIC: [u8; 4] = *b"PKFL";
+const VERSION: u8 = 1;
+const HEADER_LEN: usize = 24;
+const CHECKSUM_LEN: usize = 4;
+
+/// The CRC32 (IEEE) lookup table, built at compile time
+#[allow(clippy::cast_possible_truncation)]
+const CRC_TABLE: [u32; 256] = {
+    let mut table = [0; 256];
+    let mut idx = 0;
+    while idx < 256 {
+        let mut crc = idx as u32;
+        let mut bit = 0;
+        while bit < 8 {
+            crc = if crc & 1 == 1 { (crc >> 1) ^ 0xEDB8_8320 } else { crc >> 1 };
+            bit += 1;
+        }
+        table[idx] = crc;
+        idx += 1;
+    }
+    table
+};
+/// Reads a little endian `u64` from the first 8 bytes
+fn read_u64(bytes: &[u8]) -> u64 {
+    u64::from_le_bytes(std::array::from_fn(|idx| bytes[idx]))
+}
+fn crc32(bytes: &[u8]) -> u32 {
+    !bytes.iter().fold(u32::MAX, |crc, byte| (crc >> 8) ^ CRC_TABLE[usize::try_from((crc ^ u32::from(*byte)) & 0xFF).expect("Infalible")])
+}
+#[derive(Debug)]
+///Represents errors that can occur when reading or writing a bitmap file
+pub enum FormatError {
+    Io(io::Error),
+    BadMagic,
+    UnsupportedVersion { version: u8 },
+    UnknownEncoding { encoding: u8 },
+    Truncated { expected: usize, found: usize },
+    ChecksumMismatch { stored: u32, computed: u32 },
+    Corrupt { reason: &'static str },
+    Flags(FlagLsError),
+}
+impl Error for FormatError {}
+impl Display for FormatError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self {
+            Self::Io(err) => write!(f, "{err}"),
+            Self::BadMagic => write!(f, "not a bitmap file, the magic bytes do not match"),
+            Self::UnsupportedVersion { version } => write!(f, "unsupported bitmap file version {version}, only version {VERSION} is supported"),
+            Self::UnknownEncoding { encoding } => write!(f, "unknown bitmap encoding {encoding}"),
+            Self::Truncated { expected, found } => write!(f, "bitmap file is truncated, expected {expected} bytes but found {found}"),
+            Self::ChecksumMismatch { stored, computed } => write!(f, "bitmap file checksum {stored:08x} does not match contents, which have checksum {computed:08x}"),
+            Self::Corrupt { reason } => write!(f, "bitmap file is corrupt: {reason}"),
+            Self::Flags(err) => write!(f, "{err}"),
+        }
+    }
+}
+impl From<FlagLsError> for FormatError {
+    fn from(value: FlagLsError) -> Self {
+        Self::Flags(value)
+    }
+}
+impl From<io::Error> for FormatError {
+    fn from(value: io::Error) -> Self {
+        Self::Io(value)
+    }
+}
+/// How the flags are stored in a bitmap file
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Encoding {
+    /// Every flag as one bit, least significant bit of each byte first
+    Raw,
+    /// Lengths of alternating runs of false and true flags, starting with false, as LEB128 varints
+    RunLength,
+}
+impl Encoding {
+    const fn tag(self) -> u8 {
+        match self {
+            Self::Raw => 0,
+            Self::RunLength => 1,
+        }
+    }
+    const fn from_tag(tag: u8) -> Result<Self, FormatError> {
+        match tag {
+            0 => Ok(Self::Raw),
+            1 => Ok(Self::RunLength),
+            encoding => Err(FormatError::UnknownEncoding { encoding }),
+        }
+    }
+}
+fn push_varint(out: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        out.push(u8::try_from(value & 0x7F).expect("Infalible") | 0x80);
+        value >>= 7;
+    }
+    out.push(u8::try_from(value).expect("Infalible"));
+}
+/// Reads a varint from the start of `bytes`, returning it and the number of bytes used
+fn read_varint(bytes: &[u8]) -> Result<(u64, usize), FormatError> {
+    let mut value = 0;
+    for (idx, byte) in bytes.iter().enumerate().take(10) {
+        let part = u64::from(byte & 0x7F);
+        if idx == 9 && part > 1 {
+            return Err(FormatError::Corrupt { reason: "run length overflows a u64" });
+        }
+        value |= part << (7 * idx);
+        if byte & 0x80 == 0 {
+            return Ok((value, idx + 1));
+        }
+    }
+    Err(FormatError::Corrupt { reason: "unterminated run length" })
+}
+fn raw_payload<T: FlagLs>(flags: &T) -> Vec<u8> {
+    let len = flags.len();
+    (0..len.div_ceil(8))
+        .map(|idx| u8::try_from(flags.get_bits(idx * 8..(idx * 8 + 8).min(len))).expect("Infalible"))
+        .collect()
+}
+/// Calls `f` with the length of each alternating run of flags, starting with a run of false flags that may be empty
+fn for_each_run<T: FlagLs>(flags: &T, mut f: impl FnMut(u64)) {
+    let mut current = false;
+    let mut run = 0_u64;
+    for flag in flags.iter() {
+        if flag != current {
+            f(run);
+            current = flag;
+            run = 0;
+        }
+        run += 1;
+    }
+    if run > 0 {
+        f(run);
+    }
+}
+/// The number of bytes [`push_varint`] uses for `value`
+fn varint_len(value: u64) -> usize {
+    usize::try_from((u64::BITS - value.leading_zeros()).div_ceil(7).max(1)).expect("Infalible")
+}
+fn run_length_payload<T: FlagLs>(flags: &T) -> Vec<u8> {
+    let mut out = Vec::new();
+    for_each_run(flags, |run| push_varint(&mut out, run));
+    out
+}
+/// Encodes a list of flags as a complete bitmap file, with the given encoding
+///
+/// [`FlagLs::write_to`] picks the smaller encoding automatically
+/// # Panics
+/// Panics if the list is longer than `u64::MAX` flags
+/// # Examples
+/// ```
+/// # use std::error::Error;
+/// #
+/// # fn main() -> Result<(), Box<dyn Error>> {
+/// use packed_flags::{encode_bitmap, BitmapFile, Blong, Encoding};
+/// use packed_flags::FlagLs;
+///
+/// let mut flags=Blong::all_false(10_000);
+/// flags.set(5000,true);
+/// let raw=encode_bitmap(&flags,Encoding::Raw);
+/// let runs=encode_bitmap(&flags,Encoding::RunLength);
+/// assert!(runs.len()<raw.len());
+/// assert_eq!(BitmapFile::parse(&raw)?.to_flags::<Blong>()?,flags);
+/// assert_eq!(BitmapFile::parse(&runs)?.to_flags::<Blong>()?,flags);
+/// # Ok(())
+/// # }
+/// ```
+#[must_use]
+pub fn encode_bitmap<T: FlagLs>(flags: &T, encoding: Encoding) -> Vec<u8> {
+    let payload = match encoding {
+        Encoding::Raw => raw_payload(flags),
+        Encoding::RunLength => run_length_payload(flags),
+    };
+    let mut out = Vec::with_capacity(HEADER_LEN + payload.len() + CHECKSUM_LEN);
+    out.extend_from_slice(&MAGIC);
+    out.push(VERSION);
+    out.push(encoding.tag());
+    out.extend_from_slice(&[0, 0]);
+    out.extend_from_slice(&u64::try_from(flags.len()).expect("Flag lists longer than u64::MAX can not be stored").to_le_bytes());
+    out.extend_from_slice(&u64::try_from(payload.len()).expect("Infalible").to_le_bytes());
+    out.extend_from_slice(&payload);
+    out.extend_from_slice(&crc32(&out).to_le_bytes());
+    out
+}
+/// Encodes a list of flags as a bitmap file, in whichever encoding is smaller
+pub fn write<T: FlagLs>(flags: &T, mut writer: impl Write) -> Result<(), FormatError> {
+    let mut run_length_len = 0;
+    for_each_run(flags, |run| run_length_len += varint_len(run));
+    let encoding = if run_length_len < flags.len().div_ceil(8) { Encoding::RunLength } else { Encoding::Raw };
+    writer.write_all(&encode_bitmap(flags, encoding))?;
+    Ok(())
+}
+/// Reads exactly `len` bytes, or as many as there are if the reader ends first, without trusting `len` for the allocation
+fn read_up_to(reader: &mut impl Read, len: u64, out: &mut Vec<u8>) -> Result<usize, FormatError> {
+    Ok(reader.take(len).read_to_end(out)?)
+}
+/// Reads a whole bitmap file and decodes it
+pub fn read<T: FlagLs>(mut reader: impl Read) -> Result<T, FormatError> {
+    let mut bytes = Vec::with_capacity(HEADER_LEN);
+    let found = read_up_to(&mut reader, HEADER_LEN as u64, &mut bytes)?;
+    if found < HEADER_LEN {
+        return Err(FormatError::Truncated { expected: HEADER_LEN, found });
+    }
+    if bytes[..4] != MAGIC {
+        return Err(FormatError::BadMagic);
+    }
+    let payload_len = read_u64(&bytes[16..24]);
+    let rest = payload_len.saturating_add(CHECKSUM_LEN as u64);
+    let found = read_up_to(&mut reader, rest, &mut bytes)?;
+    if (found as u64) < rest {
+        return Err(FormatError::Truncated { expected: usize::try_from(rest).unwrap_or(usize::MAX).saturating_add(HEADER_LEN), found: HEADER_LEN + found });
+    }
+    BitmapFile::parse(&bytes)?.to_flags()
+}
+/// A parsed bitmap file which borrows its contents, so a file can be read straight from a buffer or memory map without copying
+///
+/// Parsing checks the header and checksum, and that run lengths add up to the number of flags.
+/// Files are written with [`FlagLs::write_to`] or [`encode_bitmap`]
+///
+/// All integers are little endian. The layout is
+/// - 4 bytes magic, `PKFL`
+/// - 1 byte version, currently 1
+/// - 1 byte [`Encoding`], 0 for raw and 1 for run length
+/// - 2 reserved bytes, which must be zero
+/// - 8 bytes number of flags
+/// - 8 bytes payload length, then the payload
+/// - 4 bytes CRC32 of everything before it
+/// # Examples
+/// ```
+/// # use std::error::Error;
+/// #
+/// # fn main() -> Result<(), Box<dyn Error>> {
+/// use packed_flags::{BitmapFile, Encoding, B64};
+/// use packed_flags::FlagLs;
+///
+/// let flags=B64::from_iter(vec![true,false,true,true]);
+/// let mut bytes=Vec::new();
+/// flags.write_to(&mut bytes)?;
+///
+/// let file=BitmapFile::parse(&bytes)?;
+/// assert_eq!(file.len(),4);
+/// assert_eq!(file.encoding(),Encoding::Raw);
+/// assert_eq!(file.view().map(|view| view.count_ones()),Some(3));
+/// assert!(file.iter().eq(flags.iter()));
+///
+/// bytes[24]^=1;
+/// assert!(BitmapFile::parse(&bytes).is_err());
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Copy, Debug)]
+pub struct BitmapFile<'a> {
+    len: usize,
+    encoding: Encoding,
+    payload: &'a [u8],
+}
+impl<'a> BitmapFile<'a> {
+    /// Parses and checks a complete bitmap file
+    /// # Errors
+    /// Errors if the file is truncated, has the wrong magic bytes, an unsupported version or encoding, a checksum which does not match,
+    /// or a payload which does not describe exactly the number of flags in the header
+    pub fn parse(bytes: &'a [u8]) -> Result<Self, FormatError> {
+        if bytes.len() < HEADER_LEN + CHECKSUM_LEN {
+            if bytes.len() >= MAGIC.len() && bytes[..MAGIC.len()] != MAGIC {
+                return Err(FormatError::BadMagic);
+            }
+            return Err(FormatError::Truncated { expected: HEADER_LEN + CHECKSUM_LEN, found: bytes.len() });
+        }
+        if bytes[..4] != MAGIC {
+            return Err(FormatError::BadMagic);
+        }
+        if bytes[4] != VERSION {
+            return Err(FormatError::UnsupportedVersion { version: bytes[4] });
+        }
+        let encoding = Encoding::from_tag(bytes[5])?;
+        if bytes[6..8] != [0, 0] {
+            return Err(FormatError::Corrupt { reason: "reserved header bytes are not zero" });
+        }
+        let len = usize::try_from(read_u64(&bytes[8..16]))
+            .map_err(|_| FormatError::Corrupt { reason: "bit length does not fit in a usize" })?;
+        let payload_len = read_u64(&bytes[16..24]);
+        let expected = usize::try_from(payload_len)
+            .ok()
+            .and_then(|payload_len| payload_len.checked_add(HEADER_LEN + CHECKSUM_LEN))
+            .unwrap_or(usize::MAX);
+        if bytes.len() < expected {
+            return Err(FormatError::Truncated { expected, found: bytes.len() });
+        }
+        if bytes.len() > expected {
+            return Err(FormatError::Corrupt { reason: "trailing bytes after the checksum" });
+        }
+        let (contents, checksum) = bytes.split_at(expected - CHECKSUM_LEN);
+        let stored = u32::from_le_bytes(std::array::from_fn(|idx| checksum[idx]));
+        let computed = crc32(contents);
+        if stored != computed {
+            return Err(FormatError::ChecksumMismatch { stored, computed });
+        }
+        let out = Self { len, encoding, payload: &contents[HEADER_LEN..] };
+        match encoding {
+            Encoding::Raw if out.payload.len() != len.div_ceil(8) => {
+                return Err(FormatError::Corrupt { reason: "raw payload length does not match the bit length" });
+            }
+            Encoding::RunLength => out.check_runs()?,
+            Encoding::Raw => {}
+        }
+        Ok(out)
+    }
+    /// Check the runs add up to exactly the number of flags
+    fn check_runs(&self) -> Result<(), FormatError> {
+        let mut total = 0_usize;
+        let mut pos = 0;
+        while pos < self.payload.len() {
+            let (run, used) = read_varint(&self.payload[pos..])?;
+            total = usize::try_from(run)
+                .ok()
+                .and_then(|run| total.checked_add(run))
+                .ok_or(FormatError::Corrupt { reason: "runs are longer than the bit length" })?;
+            pos += used;
+        }
+        if total == self.len {
+            Ok(())
+        } else {
+            Err(FormatError::Corrupt { reason: "runs do not add up to the bit length" })
+        }
+    }
+    /// The number of flags in the file
+    #[must_use]
+    pub const fn len(&self) -> usize {
+        self.len
+    }
+    /// Returns true when the file holds no flags
+    #[must_use]
+    pub const fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+    /// How the flags are stored
+    #[must_use]
+    pub const fn encoding(&self) -> Encoding {
+        self.encoding
+    }
+    /// A view of the flags straight from the file, if they are stored raw
+    #[must_use]
+    pub fn view(&self) -> Option<FlagView<'a>> {
+        match self.encoding {
+            Encoding::Raw => FlagView::from_bytes(self.payload, self.len, BitOrder::Lsb0).ok(),
+            Encoding::RunLength => None,
+        }
+    }
+    /// Get an iterator over the flags, decoding them as it goes
+    pub fn iter(&self) -> impl Iterator<Item = bool> + 'a {
+        let view = self.view();
+        let raw = view.into_iter().flat_map(|view| view.iter());
+        let mut payload = if view.is_some() { &[][..] } else { self.payload };
+        let mut current = true;
+        let runs = std::iter::from_fn(move || {
+            let (run, used) = read_varint(payload).ok()?;
+            payload = &payload[used..];
+            current = !current;
+            Some(std::iter::repeat_n(current, usize::try_from(run).ok()?))
+        })
+        .flatten();
+        raw.chain(runs)
+    }
+    /// Decodes the flags into a list of flags
+    /// # Errors
+    /// Errors if there are more flags than `T` can hold
+    #[allow(clippy::missing_panics_doc)]
+    pub fn to_flags<T: FlagLs>(&self) -> Result<T, FormatError> {
+        if self.len > T::MAX_LENGTH {
+            return Err(FlagLsError::MaximumLengthExceeded { mx_len: T::MAX_LENGTH, attempt_len: self.len }.into());
+        }
+        let mut out = T::default();
+        out.try_set_len(self.len)?;
+        match self.encoding {
+            Encoding::Raw => {
+                for (idx, chunk) in self.payload.chunks(16).enumerate() {
+                    let mut word = [0; 16];
+                    word[..chunk.len()].copy_from_slice(chunk);
+                    let start = idx * 128;
+                    out.set_bits(start..self.len.min(start + 128), u128::from_le_bytes(word));
+                }
+            }
+            Encoding::RunLength => {
+                let (mut pos, mut start, mut flag) = (0, 0, false);
+                while pos < self.payload.len() {
+                    let (run, used) = read_varint(&self.payload[pos..])?;
+                    let end = start + usize::try_from(run).expect("Runs are checked against the length when parsing");
+                    if flag {
+                        fill(&mut out, start..end);
+                    }
+                    (pos, start, flag) = (pos + used, end, !flag);
+                }
+            }
+        }
+        Ok(out)
+    }
+}
@@ -0,0 +1,505 @@
+use std::{fmt::{self, Debug, Formatter, Write}, ops::{BitXorAssign, Mul}};
+
+use crate::{flag_debug, BitOrder, Blong, FlagLs, FlagView};
+/// A two dimensional matrix of flags, useful for representing relations
+///
+/// Rows are stored one after the other in a single buffer, each padded to a whole number of `usize`s
+/// # Examples
+/// ```
+/// use packed_flags::BMatrix;
+///
+/// // 0 -> 1 -> 2
+/// let mut edges=BMatrix::new(3,3);
+/// edges.set(0,1,true);
+/// edges.set(1,2,true);
+///
+/// let reach=edges.transitive_closure();
+/// assert_eq!(reach.get(0,2),Some(true));
+/// assert_eq!(reach.get(2,0),Some(false));
+/// ```
+#[derive(PartialEq, Eq, Default, Clone, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize), serde(try_from = "RawBMatrix"))]
+pub struct BMatrix {
+    inner: Vec<usize>,
+    rows: usize,
+    cols: usize,
+}
+/// The fields of a [`BMatrix`] as they are deserialized, before the buffer is checked against the size
+#[cfg(feature = "serde")]
+#[derive(serde::Deserialize)]
+struct RawBMatrix {
+    inner: Vec<usize>,
+    rows: usize,
+    cols: usize,
+}
+#[cfg(feature = "serde")]
+impl TryFrom<RawBMatrix> for BMatrix {
+    type Error = String;
+    fn try_from(value: RawBMatrix) -> Result<Self, Self::Error> {
+        let RawBMatrix { inner, rows, cols } = value;
+        match Self::words_for(rows, cols) {
+            Some(words) if words == inner.len() => Ok(Self { inner, rows, cols }),
+            _ => Err(format!("a {rows}x{cols} matrix can not be stored in {} words", inner.len())),
+        }
+    }
+}
+impl BMatrix {
+    const INNER_SIZE: usize = usize::BITS as usize;
+    /// The number of words needed to store a matrix of the given size, if it fits in a usize
+    const fn words_for(rows: usize, cols: usize) -> Option<usize> {
+        rows.checked_mul(cols.div_ceil(Self::INNER_SIZE))
+    }
+    /// Create a new matrix of the given size with every flag false
+    /// # Panics
+    /// If the matrix would need more than `usize::MAX` words
+    #[must_use]
+    pub fn new(rows: usize, cols: usize) -> Self {
+        let words = Self::words_for(rows, cols).expect("Cannot create a matrix with more than usize::MAX words");
+        Self { inner: vec![0; words], rows, cols }
+    }
+    /// Create a square matrix with only the diagonal set
+    #[must_use]
+    pub fn identity(size: usize) -> Self {
+        let mut out = Self::new(size, size);
+        for i in 0..size {
+            out.set(i, i, true);
+        }
+        out
+    }
+    /// The number of rows in the matrix
+    #[must_use]
+    pub const fn rows(&self) -> usize {
+        self.rows
+    }
+    /// The number of columns in the matrix
+    #[must_use]
+    pub const fn cols(&self) -> usize {
+        self.cols
+    }
+    const fn row_words(&self) -> usize {
+        self.cols.div_ceil(Self::INNER_SIZE)
+    }
+    fn row_slice(&self, row: usize) -> &[usize] {
+        let width = self.row_words();
+        &self.inner[row * width..(row + 1) * width]
+    }
+    fn row_slice_mut(&mut self, row: usize) -> &mut [usize] {
+        let width = self.row_words();
+        &mut self.inner[row * width..(row + 1) * width]
+    }
+    /// Get the flag at the given row and column, if it exists, otherwise returns None
+    #[must_use]
+    pub fn get(&self, row: usize, col: usize) -> Option<bool> {
+        if row < self.rows && col < self.cols {
+            let word = self.row_slice(row)[col / Self::INNER_SIZE];
+            Some((word >> (col % Self::INNER_SIZE)) & 1 == 1)
+        } else {
+            None
+        }
+    }
+    /// Set the flag at the given row and column
+    /// # Panics
+    /// If the row or column is out of bounds
+    pub fn set(&mut self, row: usize, col: usize, flag: bool) {
+        assert!(row < self.rows && col < self.cols, "Cannot set ({row},{col}) in a {}x{} matrix", self.rows, self.cols);
+        let word = &mut self.row_slice_mut(row)[col / Self::INNER_SIZE];
+        let bit = 1 << (col % Self::INNER_SIZE);
+        if flag {
+            *word |= bit;
+        } else {
+            *word &= !bit;
+        }
+    }
+    /// Borrow a row as a list of flags, without copying it
+    /// # Panics
+    /// If the row is out of bounds
+    /// # Examples
+    /// ```
+    /// use packed_flags::BMatrix;
+    ///
+    /// let mut m=BMatrix::new(2,100);
+    /// m.set(1,3,true);
+    /// m.set(1,70,true);
+    /// assert_eq!(m.row(1).iter_ones().collect::<Vec<_>>(),vec![3,70]);
+    /// assert_eq!(m.row(0).count_ones(),0);
+    /// ```
+    #[must_use]
+    pub fn row(&self, row: usize) -> FlagView<'_> {
+        assert!(row < self.rows, "Cannot get row {row} of a matrix with {} rows", self.rows);
+        FlagView::from_usizes(self.row_slice(row), self.cols, BitOrder::Lsb0).expect("Rows always hold cols flags")
+    }
+    /// Get a copy of a row as a list of flags
+    /// # Panics
+    /// If the row is out of bounds
+    #[must_use]
+    pub fn row_to_blong(&self, row: usize) -> Blong {
+        assert!(row < self.rows, "Cannot get row {row} of a matrix with {} rows", self.rows);
+        Blong::initialize(self.row_slice(row).to_vec(), self.cols)
+    }
+    /// Overwrite a row with a list of flags
+    ///
+    /// If the list is shorter than the row, the rest of the row is set to false. Flags beyond the end of the row are ignored
+    /// # Panics
+    /// If the row is out of bounds
+    pub fn set_row(&mut self, row: usize, flags: &Blong) {
+        assert!(row < self.rows, "Cannot set row {row} of a matrix with {} rows", self.rows);
+        let cols = self.cols;
+        let dest = self.row_slice_mut(row);
+        let src = flags.inner();
+        for (i, word) in dest.iter_mut().enumerate() {
+            *word = src.get(i).copied().unwrap_or(0);
+        }
+        Self::mask_row(dest, cols);
+    }
+    /// Zero any bits past the end of the row
+    const fn mask_row(row: &mut [usize], cols: usize) {
+        let rem = cols % Self::INNER_SIZE;
+        if let (Some(last), true) = (row.last_mut(), rem > 0) {
+            *last &= (1 << rem) - 1;
+        }
+    }
+    /// Get a copy of a column as a list of flags
+    /// # Panics
+    /// If the column is out of bounds
+    #[must_use]
+    pub fn column(&self, col: usize) -> Blong {
+        assert!(col < self.cols, "Cannot get column {col} of a matrix with {} columns", self.cols);
+        let mut words = vec![0; self.rows.div_ceil(Self::INNER_SIZE)];
+        let (t_col, m_col) = (col / Self::INNER_SIZE, col % Self::INNER_SIZE);
+        for row in 0..self.rows {
+            words[row / Self::INNER_SIZE] |= ((self.row_slice(row)[t_col] >> m_col) & 1) << (row % Self::INNER_SIZE);
+        }
+        Blong::initialize(words, self.rows)
+    }
+    /// Transpose a square block of bits in place, so bit `j` of `block[i]` ends up at bit `i` of `block[j]`
+    fn transpose_block(block: &mut [usize; Self::INNER_SIZE]) {
+        let mut width = Self::INNER_SIZE / 2;
+        let mut mask = usize::MAX >> width;
+        while width > 0 {
+            let mut k = 0;
+            while k < Self::INNER_SIZE {
+                for i in k..k + width {
+                    let t = ((block[i] >> width) ^ block[i + width]) & mask;
+                    block[i] ^= t << width;
+                    block[i + width] ^= t;
+                }
+                k += 2 * width;
+            }
+            width /= 2;
+            mask ^= mask << width;
+        }
+    }
+    /// Returns the transpose of the matrix, swapping rows and columns
+    ///
+    /// Works on whole blocks of `usize::BITS` by `usize::BITS` flags at a time
+    /// # Examples
+    /// ```
+    /// use packed_flags::BMatrix;
+    ///
+    /// let mut m=BMatrix::new(2,3);
+    /// m.set(0,2,true);
+    /// let t=m.transpose();
+    /// assert_eq!((t.rows(),t.cols()),(3,2));
+    /// assert_eq!(t.get(2,0),Some(true));
+    /// ```
+    #[must_use]
+    pub fn transpose(&self) -> Self {
+        let mut out = Self::new(self.cols, self.rows);
+        let in_width = self.row_words();
+        let out_width = out.row_words();
+        let mut block = [0; Self::INNER_SIZE];
+        for block_row in 0..self.rows.div_ceil(Self::INNER_SIZE) {
+            for block_col in 0..in_width {
+                for (k, word) in block.iter_mut().enumerate() {
+                    let row = block_row * Self::INNER_SIZE + k;
+                    *word = if row < self.rows { self.inner[row * in_width + block_col] } else { 0 };
+                }
+                Self::transpose_block(&mut block);
+                for (k, word) in block.iter().enumerate() {
+                    let row = block_col * Self::INNER_SIZE + k;
+                    if row < out.rows {
+                        out.inner[row * out_width + block_row] = *word;
+                    }
+                }
+            }
+        }
+        out
+    }
+    /// Boolean matrix multiplication, where `out[i][j]` is true if `self[i][k]` and `rhs[k][j]` are both true for some `k`
+    /// # Panics
+    /// If the number of columns of `self` is not the number of rows of `rhs`
+    /// # Examples
+    /// ```
+    /// use packed_flags::BMatrix;
+    ///
+    /// let mut a=BMatrix::new(1,2);
+    /// a.set(0,1,true);
+    /// let mut b=BMatrix::new(2,2);
+    /// b.set(1,0,true);
+    /// assert_eq!(a.multiply(&b).get(0,0),Some(true));
+    /// ```
+    #[must_use]
+    pub fn multiply(&self, rhs: &Self) -> Self {
+        assert_eq!(self.cols, rhs.rows, "Cannot multiply a {}x{} matrix by a {}x{} matrix", self.rows, self.cols, rhs.rows, rhs.cols);
+        let mut out = Self::new(self.rows, rhs.cols);
+        let width = out.row_words();
+        for i in 0..self.rows {
+            for k in (0..self.cols).filter(|k| self.get(i, *k) == Some(true)) {
+                let src = rhs.row_slice(k);
+                for (dest, word) in out.inner[i * width..(i + 1) * width].iter_mut().zip(src) {
+                    *dest |= *word;
+                }
+            }
+        }
+        out
+    }
+    /// Returns the transitive closure of the relation represented by a square matrix
+    ///
+    /// `out[i][j]` is true if there is a path of one or more steps from `i` to `j`
+    /// # Panics
+    /// If the matrix is not square
+    #[must_use]
+    pub fn transitive_closure(&self) -> Self {
+        assert_eq!(self.rows, self.cols, "Cannot take the transitive closure of a {}x{} matrix", self.rows, self.cols);
+        let mut out = self.clone();
+        let width = out.row_words();
+        for k in 0..out.rows {
+            for i in 0..out.rows {
+                if i != k && out.get(i, k) == Some(true) {
+                    for w in 0..width {
+                        let word = out.inner[k * width + w];
+                        out.inner[i * width + w] |= word;
+                    }
+                }
+            }
+        }
+        out
+    }
+}
+/// Linear algebra over GF(2), where addition is xor and multiplication is and
+impl BMatrix {
+    /// Xor row `src` into row `dest`
+    fn xor_row_into(&mut self, src: usize, dest: usize) {
+        let width = self.row_words();
+        for w in 0..width {
+            let word = self.inner[src * width + w];
+            self.inner[dest * width + w].bitxor_assign(word);
+        }
+    }
+    fn swap_rows(&mut self, a: usize, b: usize) {
+        let width = self.row_words();
+        for w in 0..width {
+            self.inner.swap(a * width + w, b * width + w);
+        }
+    }
+    /// Puts the matrix into reduced row echelon form using Gaussian elimination, returning the pivot column of each nonzero row
+    ///
+    /// Row operations work on whole words at a time
+    /// # Examples
+    /// ```
+    /// use packed_flags::{BMatrix, Blong};
+    /// use packed_flags::FlagLs;
+    ///
+    /// let mut m=BMatrix::from(vec![
+    ///     Blong::from_iter(vec![true,true,false]),
+    ///     Blong::from_iter(vec![true,true,true]),
+    /// ]);
+    /// assert_eq!(m.reduce_row_echelon(),vec![0,2]);
+    /// assert_eq!(m.row_to_blong(0),Blong::from_iter(vec![true,true,false]));
+    /// assert_eq!(m.row_to_blong(1),Blong::from_iter(vec![false,false,true]));
+    /// ```
+    pub fn reduce_row_echelon(&mut self) -> Vec<usize> {
+        let mut pivots = Vec::new();
+        for col in 0..self.cols {
+            let row = pivots.len();
+            if row == self.rows {
+                break;
+            }
+            let Some(pivot) = (row..self.rows).find(|r| self.get(*r, col) == Some(true)) else {
+                continue;
+            };
+            self.swap_rows(pivot, row);
+            for other in 0..self.rows {
+                if other != row && self.get(other, col) == Some(true) {
+                    self.xor_row_into(row, other);
+                }
+            }
+            pivots.push(col);
+        }
+        pivots
+    }
+    /// Matrix multiplication over GF(2), where `out[i][j]` is true if `self[i][k]` and `rhs[k][j]` are both true for an odd number of `k`
+    ///
+    /// Compare with [`multiply`][BMatrix::multiply], which is true if there is at least one such `k`
+    /// # Panics
+    /// If the number of columns of `self` is not the number of rows of `rhs`
+    #[must_use]
+    pub fn gf2_multiply(&self, rhs: &Self) -> Self {
+        assert_eq!(self.cols, rhs.rows, "Cannot multiply a {}x{} matrix by a {}x{} matrix", self.rows, self.cols, rhs.rows, rhs.cols);
+        let mut out = Self::new(self.rows, rhs.cols);
+        let width = out.row_words();
+        for i in 0..self.rows {
+            for k in (0..self.cols).filter(|k| self.get(i, *k) == Some(true)) {
+                let src = rhs.row_slice(k);
+                for (dest, word) in out.inner[i * width..(i + 1) * width].iter_mut().zip(src) {
+                    dest.bitxor_assign(*word);
+                }
+            }
+        }
+        out
+    }
+    /// The rank of the matrix over GF(2)
+    #[must_use]
+    pub fn rank(&self) -> usize {
+        self.clone().reduce_row_echelon().len()
+    }
+    /// Finds some `x` with `self * x = b` over GF(2), or None if there is no solution
+    ///
+    /// When there are many solutions, every free variable is set to false
+    /// # Panics
+    /// If the length of `b` is not the number of rows
+    /// # Examples
+    /// ```
+    /// use packed_flags::{BMatrix, Blong};
+    /// use packed_flags::FlagLs;
+    ///
+    /// // x0 ^ x1 = 1, x1 = 1
+    /// let a=BMatrix::from(vec![
+    ///     Blong::from_iter(vec![true,true]),
+    ///     Blong::from_iter(vec![false,true]),
+    /// ]);
+    /// let x=a.solve(&Blong::from_iter(vec![true,true]));
+    /// assert_eq!(x,Some(Blong::from_iter(vec![false,true])));
+    /// ```
+    #[must_use]
+    pub fn solve(&self, b: &Blong) -> Option<Blong> {
+        assert_eq!(b.len(), self.rows, "Cannot solve a system with {} equations using {} values", self.rows, b.len());
+        let mut augmented = Self::new(self.rows, self.cols + 1);
+        for row in 0..self.rows {
+            let mut flags = self.row_to_blong(row);
+            flags.push(b.get(row) == Some(true));
+            augmented.set_row(row, &flags);
+        }
+        let pivots = augmented.reduce_row_echelon();
+        if pivots.last() == Some(&self.cols) {
+            return None;
+        }
+        let mut out = Blong::all_false(self.cols);
+        for (row, col) in pivots.into_iter().enumerate() {
+            out.set(col, augmented.get(row, self.cols) == Some(true));
+        }
+        Some(out)
+    }
+    /// A basis for the null space, every `x` with `self * x = 0` is a sum of some of these
+    /// # Examples
+    /// ```
+    /// use packed_flags::{BMatrix, Blong};
+    /// use packed_flags::FlagLs;
+    ///
+    /// let a=BMatrix::from(vec![Blong::from_iter(vec![true,true,false])]);
+    /// assert_eq!(a.null_space(),vec![
+    ///     Blong::from_iter(vec![true,true,false]),
+    ///     Blong::from_iter(vec![false,false,true]),
+    /// ]);
+    /// ```
+    #[must_use]
+    pub fn null_space(&self) -> Vec<Blong> {
+        let mut reduced = self.clone();
+        let pivots = reduced.reduce_row_echelon();
+        let mut out = Vec::new();
+        let mut next_pivot = pivots.iter().peekable();
+        for free in 0..self.cols {
+            if next_pivot.peek() == Some(&&free) {
+                next_pivot.next();
+                continue;
+            }
+            let mut basis = Blong::all_false(self.cols);
+            basis.set(free, true);
+            for (row, col) in pivots.iter().enumerate() {
+                basis.set(*col, reduced.get(row, free) == Some(true));
+            }
+            out.push(basis);
+        }
+        out
+    }
+    /// The inverse of a square matrix over GF(2), or None if it is singular
+    /// # Panics
+    /// If the matrix is not square
+    /// # Examples
+    /// ```
+    /// use packed_flags::BMatrix;
+    ///
+    /// let mut m=BMatrix::identity(3);
+    /// m.set(0,2,true);
+    /// let inv=m.inverse().expect("upper triangular matrices are invertible");
+    /// assert_eq!(m.gf2_multiply(&inv),BMatrix::identity(3));
+    /// assert_eq!(BMatrix::new(2,2).inverse(),None);
+    /// ```
+    #[must_use]
+    pub fn inverse(&self) -> Option<Self> {
+        assert_eq!(self.rows, self.cols, "Cannot invert a {}x{} matrix", self.rows, self.cols);
+        let size = self.rows;
+        let mut augmented = Self::new(size, 2 * size);
+        for row in 0..size {
+            let mut flags = self.row_to_blong(row);
+            flags.set_len(2 * size);
+            flags.set(size + row, true);
+            augmented.set_row(row, &flags);
+        }
+        let pivots = augmented.reduce_row_echelon();
+        if pivots.len() < size || pivots.last().is_some_and(|col| *col >= size) {
+            return None;
+        }
+        let mut out = Self::new(size, size);
+        for row in 0..size {
+            for col in 0..size {
+                out.set(row, col, augmented.get(row, size + col) == Some(true));
+            }
+        }
+        Some(out)
+    }
+}
+impl Mul<&BMatrix> for &BMatrix {
+    type Output = BMatrix;
+    fn mul(self, rhs: &BMatrix) -> Self::Output {
+        self.multiply(rhs)
+    }
+}
+/// Writes the size of the matrix followed by one row of `0`s and `1`s per line
+///
+/// Long rows are shortened the same way as a list of flags
+/// # Examples
+/// ```
+/// use packed_flags::BMatrix;
+///
+/// let mut matrix=BMatrix::new(2,3);
+/// matrix.set(0,1,true);
+/// matrix.set(1,2,true);
+/// assert_eq!(format!("{matrix:?}"),"BMatrix(2x3)[\n010\n001\n]");
+/// ```
+impl Debug for BMatrix {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "BMatrix({}x{})[", self.rows, self.cols)?;
+        for row in 0..self.rows {
+            f.write_char('\n')?;
+            flag_debug::write_flags(&self.row_to_blong(row), f)?;
+        }
+        f.write_str("\n]")
+    }
+}
+impl From<Vec<Blong>> for BMatrix {
+    /// Builds a matrix with one row per list, padding shorter rows with false
+    fn from(value: Vec<Blong>) -> Self {
+        let cols = value.iter().map(FlagLs::len).max().unwrap_or(0);
+        let mut out = Self::new(value.len(), cols);
+        for (i, row) in value.iter().enumerate() {
+            out.set_row(i, row);
+        }
+        out
+    }
+}
+impl From<BMatrix> for Vec<Blong> {
+    fn from(value: BMatrix) -> Self {
+        (0..value.rows).map(|i| value.row_to_blong(i)).collect()
+    }
+}
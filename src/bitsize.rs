@@ -1,10 +1,10 @@
 use std::{ops::{
-    BitAnd, BitAndAssign, BitOr, BitOrAssign, BitXor, BitXorAssign, Index, Not, Shl, ShlAssign,
-    Shr, ShrAssign, Sub,}, fmt::{UpperHex, LowerHex, Octal, Binary}};
+    BitAnd, BitAndAssign, BitOr, BitOrAssign, BitXor, BitXorAssign, Index, Not, Range, Shl, ShlAssign,
+    Shr, ShrAssign, Sub,}, fmt::{Debug, UpperHex, LowerHex, Octal, Binary}};
 
-use crate::{flag_iter, Blong, FlagLs, B128, B32, B64, FlagLsError};
+use crate::{flag_debug, flag_iter, flagls::field_width, BAuto, Blong, FlagLs, B128, B32, B64, FlagLsError};
 
-#[derive(PartialEq, Eq, Default, Clone, Copy, Debug, Hash)]
+#[derive(PartialEq, Eq, Default, Clone, Copy, Hash)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 /// A list of flags/bitfield up to the size of a pointer
 /// 
@@ -14,12 +14,10 @@ pub struct Bsize {
     len: usize,
 }
 impl Bsize {
-    fn lower_mask(point: usize) -> usize {
-        if point > Self::MAX_LENGTH {
-            panic!(
-                "Cannot create mask more than {} bits for Bsize",
-                Self::MAX_LENGTH
-            )
+    /// A mask of the bits below `point`, which is every bit once `point` reaches the width of a `usize`
+    const fn lower_mask(point: usize) -> usize {
+        if point >= usize::BITS as usize {
+            usize::MAX
         } else {
             (1 << point) - 1
         }
@@ -46,12 +44,17 @@ impl Bsize {
     pub const fn as_inner(self) -> usize {
         self.inner
     }
-    fn uper_mask(point: usize) -> usize {
-        if point > Self::MAX_LENGTH {
-            panic!("Cannot mask above the end of the list");
-        } else {
-            usize::MAX - (1 << point) + 1
-        }
+    /// A mask of the bits at and above `point`, which is no bits once `point` reaches the width of a `usize`
+    const fn uper_mask(point: usize) -> usize {
+        !Self::lower_mask(point)
+    }
+    /// Shifts `inner` left by `rhs`, where shifting by the whole width or more moves every flag off the end
+    fn shifted_left(inner: usize, rhs: usize) -> usize {
+        u32::try_from(rhs).ok().and_then(|rhs| inner.checked_shl(rhs)).unwrap_or(0)
+    }
+    /// Shifts `inner` right by `rhs`, where shifting by the whole width or more moves every flag off the end
+    fn shifted_right(inner: usize, rhs: usize) -> usize {
+        u32::try_from(rhs).ok().and_then(|rhs| inner.checked_shr(rhs)).unwrap_or(0)
     }
     /// Create a new blank empty list of flags
     #[must_use]
@@ -92,7 +95,7 @@ impl FlagLs for Bsize {
     fn set_len(&mut self, new_len: usize) {
         assert!(new_len <= Self::MAX_LENGTH, "Cannot set length to a length larger than {} for Bsize", Self::MAX_LENGTH);
         self.len = new_len;
-        self.inner &= (1 << new_len) - 1;
+        self.inner &= Self::lower_mask(new_len);
     }
 
     fn insert(&mut self, index: usize, flag: bool) {
@@ -136,17 +139,90 @@ impl FlagLs for Bsize {
 
     fn set(&mut self, index: usize, flag: bool) {
         if index < self.len() {
-            let uper = self.inner & Self::uper_mask(index + 1);
-            let lower = self.inner & Self::lower_mask(index);
-            self.inner = uper + ((usize::from(flag)) << index) + lower;
+            self.inner = (self.inner & !(1 << index)) | (usize::from(flag) << index);
         } else {
             panic!("Cannot set out of bounds")
         }
     }
 
-    fn iter(&self) -> flag_iter::Iter<Self> {
+    fn iter(&self) -> flag_iter::Iter<'_, Self> {
         flag_iter::Iter::new(self)
     }
+
+    fn try_get_bits(&self, range: Range<usize>) -> Result<u128, FlagLsError> {
+        let width = field_width(&range, self.len)?;
+        if width == 0 {
+            return Ok(0);
+        }
+        Ok(u128::try_from((self.inner >> range.start) & Self::lower_mask(width)).expect("Infalible"))
+    }
+
+    fn try_set_bits(&mut self, range: Range<usize>, value: u128) -> Result<(), FlagLsError> {
+        let width = field_width(&range, self.len)?;
+        if width > 0 {
+            let mask = Self::lower_mask(width);
+            let value = usize::try_from(value & u128::try_from(mask).expect("Infalible")).expect("Infalible");
+            self.inner = (self.inner & !(mask << range.start)) | (value << range.start);
+        }
+        Ok(())
+    }
+
+    fn and_count(&self, other: &Self) -> usize {
+        usize::try_from((self.inner & other.inner).count_ones()).expect("Infalible")
+    }
+
+    fn or_count(&self, other: &Self) -> usize {
+        usize::try_from((self.inner | other.inner).count_ones()).expect("Infalible")
+    }
+
+    fn xor_count(&self, other: &Self) -> usize {
+        usize::try_from((self.inner ^ other.inner).count_ones()).expect("Infalible")
+    }
+
+    fn swap(&mut self, first: usize, second: usize) {
+        assert!(first < self.len && second < self.len, "Cannot swap flags {first} and {second} of a list of length {}", self.len);
+        if (self.inner >> first) & 1 != (self.inner >> second) & 1 {
+            self.inner ^= (1 << first) | (1 << second);
+        }
+    }
+
+    fn reverse(&mut self) {
+        if self.len > 0 {
+            self.inner = self.inner.reverse_bits() >> (usize::BITS as usize - self.len);
+        }
+    }
+
+    fn retain(&mut self, mut f: impl FnMut(usize, bool) -> bool) {
+        let mut out = 0;
+        let mut kept = 0;
+        for idx in 0..self.len {
+            let flag = (self.inner >> idx) & 1 == 1;
+            if f(idx, flag) {
+                out |= usize::from(flag) << kept;
+                kept += 1;
+            }
+        }
+        self.inner = out;
+        self.len = kept;
+    }
+
+    fn stable_partition(&mut self) -> usize {
+        let count = usize::try_from(self.inner.count_ones()).expect("Infalible");
+        self.inner = Self::lower_mask(count);
+        count
+    }
+
+    fn dedup(&mut self) {
+        if self.len == 0 {
+            return;
+        }
+        // a run starts wherever a flag differs from the one before it
+        let starts = (self.inner ^ (self.inner >> 1)) & Self::lower_mask(self.len).wrapping_shr(1);
+        let runs = 1 + usize::try_from(starts.count_ones()).expect("Infalible");
+        let alternating = if self.inner & 1 == 1 { usize::MAX / 3 } else { (usize::MAX / 3) << 1 };
+        self.inner = alternating & Self::lower_mask(runs);
+        self.len = runs;
+    }
 }
 impl BitAnd<Self> for Bsize {
     type Output = Self;
@@ -188,27 +264,27 @@ impl BitXorAssign<Self> for Bsize {
 impl Shl<usize> for Bsize {
     type Output = Self;
     fn shl(self, rhs: usize) -> Self::Output {
-        Self{inner:self.inner<<rhs, len:(self.len + rhs).min(Self::MAX_LENGTH)}
+        Self{inner:Self::shifted_left(self.inner, rhs), len:self.len.saturating_add(rhs).min(Self::MAX_LENGTH)}
     }
 }
 #[allow(clippy::suspicious_op_assign_impl)]
 impl ShlAssign<usize> for Bsize {
     fn shl_assign(&mut self, rhs: usize) {
-        self.inner.shl_assign(rhs);
-        self.len = (self.len + rhs).min(Self::MAX_LENGTH);
+        self.inner = Self::shifted_left(self.inner, rhs);
+        self.len = self.len.saturating_add(rhs).min(Self::MAX_LENGTH);
     }
 }
 impl Shr<usize> for Bsize {
     type Output = Self;
     fn shr(self, rhs: usize) -> Self::Output {
-        let new_len = if self.len > rhs { self.len - rhs } else { 0 };
-        Self{inner:self.inner>>rhs,len:new_len}
+        let new_len = self.len.saturating_sub(rhs);
+        Self{inner:Self::shifted_right(self.inner, rhs),len:new_len}
     }
 }
 impl ShrAssign<usize> for Bsize {
     fn shr_assign(&mut self, rhs: usize) {
-        self.inner.shr_assign(rhs);
-        self.len = if self.len > rhs { self.len - rhs } else { 0 };
+        self.inner = Self::shifted_right(self.inner, rhs);
+        self.len = self.len.saturating_sub(rhs);
     }
 }
 impl Not for Bsize {
@@ -224,6 +300,14 @@ impl Sub<Self> for Bsize {
         Self{inner:self.inner&(!rhs.inner),len:self.len}
     }
 }
+#[cfg(not(target_pointer_width = "16"))]
+impl From<B32> for Bsize{
+    fn from(value: B32) -> Self {
+        let len = value.len();
+        Self { inner: value.as_inner().try_into().expect("Infalible"), len }
+    }
+}
+#[cfg(target_pointer_width = "16")]
 impl TryFrom<B32> for Bsize{
     type Error = FlagLsError;
     fn try_from(value: B32) -> Result<Self, Self::Error> {
@@ -234,6 +318,14 @@ impl TryFrom<B32> for Bsize{
         }
     }
 }
+#[cfg(target_pointer_width = "64")]
+impl From<B64> for Bsize{
+    fn from(value: B64) -> Self {
+        let len = value.len();
+        Self { inner: value.as_inner().try_into().expect("Infalible"), len }
+    }
+}
+#[cfg(not(target_pointer_width = "64"))]
 impl TryFrom<B64> for Bsize{
     type Error = FlagLsError;
     fn try_from(value: B64) -> Result<Self, Self::Error> {
@@ -264,6 +356,18 @@ impl TryFrom<Blong> for Bsize{
         }
     }
 }
+impl TryFrom<BAuto> for Bsize{
+    type Error = FlagLsError;
+    fn try_from(value: BAuto) -> Result<Self, Self::Error> {
+        value.convert()
+    }
+}
+/// Shows the flags in order as `Bsize[0110](len=4)`, or with `{:#?}` the length, the number of true flags and the runs of true flags
+impl Debug for Bsize {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        flag_debug::fmt_flags(self, "Bsize", f)
+    }
+}
 impl UpperHex for Bsize{
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(f,"{:X}",self.inner)
@@ -0,0 +1,209 @@
+use std::io::{self, Read, Write};
+
+use crate::{Blong, FlagLs, FlagLsError};
+/// Packs values of any bit width one after another into a [`Blong`]
+///
+/// Values are written least significant bit first, so the first bit written becomes flag 0
+/// # Examples
+/// ```
+/// # use std::error::Error;
+/// #
+/// # fn main() -> Result<(), Box<dyn Error>> {
+/// use packed_flags::{BitReader, BitWriter};
+/// use packed_flags::FlagLs;
+///
+/// let mut writer=BitWriter::new();
+/// writer.write_bits(5,3);
+/// writer.write_bool(true);
+/// writer.write_bits(1234,12);
+/// let flags=writer.into_inner();
+/// assert_eq!(flags.len(),16);
+///
+/// let mut reader=BitReader::new(&flags);
+/// assert_eq!(reader.read_bits(3)?,5);
+/// assert!(reader.read_bool()?);
+/// assert_eq!(reader.read_bits(12)?,1234);
+/// assert!(reader.read_bool().is_err());
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq, Default)]
+pub struct BitWriter {
+    flags: Blong,
+}
+impl BitWriter {
+    /// Create a writer with nothing written
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+    /// Create a writer which appends to the end of an existing list of flags
+    #[must_use]
+    pub const fn from_flags(flags: Blong) -> Self {
+        Self { flags }
+    }
+    /// The number of bits written so far
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.flags.len()
+    }
+    /// Returns true if nothing has been written
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.flags.is_empty()
+    }
+    /// Writes the lowest `width` bits of `value`
+    /// # Panics
+    /// If `width` is more than 64
+    pub fn write_bits(&mut self, value: u64, width: usize) {
+        assert!(width <= 64, "Cannot write {width} bits of a u64");
+        let start = self.flags.len();
+        self.flags.set_len(start + width);
+        self.flags.set_bits(start..start + width, u128::from(value));
+    }
+    /// Writes a single flag
+    pub fn write_bool(&mut self, flag: bool) {
+        self.flags.push(flag);
+    }
+    /// Writes every flag from a list of flags, in order
+    pub fn write_flags<T: FlagLs>(&mut self, flags: &T) {
+        for flag in flags.iter() {
+            self.flags.push(flag);
+        }
+    }
+    /// Pads with false until the number of bits written is a multiple of `bits`
+    /// # Panics
+    /// If `bits` is zero
+    pub fn align_to(&mut self, bits: usize) {
+        let padded = self.flags.len().next_multiple_of(bits);
+        self.flags.set_len(padded);
+    }
+    /// Pads with false until a whole number of bytes has been written
+    pub fn align_byte(&mut self) {
+        self.align_to(8);
+    }
+    /// Get a reference to the bits written so far
+    #[must_use]
+    pub const fn as_flags(&self) -> &Blong {
+        &self.flags
+    }
+    /// Converts the writer into the bits written, consuming it
+    #[must_use]
+    #[allow(clippy::missing_const_for_fn)]
+    pub fn into_inner(self) -> Blong {
+        self.flags
+    }
+}
+/// Writes each byte as 8 bits, least significant bit first
+///
+/// Call [`align_byte`][BitWriter::align_byte] first if the bytes should start on a byte boundary
+impl Write for BitWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        for byte in buf {
+            self.write_bits(u64::from(*byte), 8);
+        }
+        Ok(buf.len())
+    }
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+/// Reads values of any bit width back out of a list of flags
+///
+/// See [`BitWriter`] for an example
+#[derive(Clone, Debug)]
+pub struct BitReader<'a> {
+    flags: &'a Blong,
+    pos: usize,
+}
+impl<'a> BitReader<'a> {
+    /// Create a reader starting at the first flag
+    #[must_use]
+    pub const fn new(flags: &'a Blong) -> Self {
+        Self { flags, pos: 0 }
+    }
+    /// The index of the next flag to be read
+    #[must_use]
+    pub const fn position(&self) -> usize {
+        self.pos
+    }
+    /// The number of flags left to read
+    #[must_use]
+    pub fn remaining(&self) -> usize {
+        self.flags.len() - self.pos
+    }
+    /// Moves to the flag at `pos`, which may be one past the last flag
+    /// # Errors
+    /// Errors if `pos` is past the end of the flags
+    pub fn seek(&mut self, pos: usize) -> Result<(), FlagLsError> {
+        if pos > self.flags.len() {
+            Err(FlagLsError::IndexOutOfBounds { idx: pos, len: self.flags.len() })
+        } else {
+            self.pos = pos;
+            Ok(())
+        }
+    }
+    /// Checks `width` more flags can be read
+    fn check(&self, width: usize) -> Result<(), FlagLsError> {
+        if width > self.remaining() {
+            Err(FlagLsError::IndexOutOfBounds { idx: self.pos + width - 1, len: self.flags.len() })
+        } else {
+            Ok(())
+        }
+    }
+    /// Reads `width` bits as an unsigned value, least significant bit first
+    /// # Errors
+    /// Errors if there are fewer than `width` flags left, in which case the position is unchanged
+    /// # Panics
+    /// If `width` is more than 64
+    pub fn read_bits(&mut self, width: usize) -> Result<u64, FlagLsError> {
+        assert!(width <= 64, "Cannot read {width} bits into a u64");
+        self.check(width)?;
+        let out = u64::try_from(self.flags.get_bits(self.pos..self.pos + width)).expect("Infalible");
+        self.pos += width;
+        Ok(out)
+    }
+    /// Reads a single flag
+    /// # Errors
+    /// Errors if there are no flags left
+    pub fn read_bool(&mut self) -> Result<bool, FlagLsError> {
+        self.check(1)?;
+        self.pos += 1;
+        Ok(self.flags[self.pos - 1])
+    }
+    /// Reads the next `len` flags into a new list of flags
+    /// # Errors
+    /// Errors if there are fewer than `len` flags left, or if `T` cannot hold `len` flags. The position is unchanged on error
+    pub fn read_flags<T: FlagLs>(&mut self, len: usize) -> Result<T, FlagLsError> {
+        self.check(len)?;
+        let out = T::try_from_iter((self.pos..self.pos + len).map(|idx| self.flags[idx]))?;
+        self.pos += len;
+        Ok(out)
+    }
+    /// Skips forward until the position is a multiple of `bits`
+    /// # Errors
+    /// Errors if that would be past the end of the flags, in which case the position is unchanged
+    /// # Panics
+    /// If `bits` is zero
+    pub fn align_to(&mut self, bits: usize) -> Result<(), FlagLsError> {
+        self.seek(self.pos.next_multiple_of(bits))
+    }
+    /// Skips forward to the next byte boundary
+    /// # Errors
+    /// Errors if that would be past the end of the flags, in which case the position is unchanged
+    pub fn align_byte(&mut self) -> Result<(), FlagLsError> {
+        self.align_to(8)
+    }
+}
+/// Reads whole bytes as 8 bits each, least significant bit first
+///
+/// Any flags left over at the end which do not make up a whole byte are not read
+impl Read for BitReader<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let count = buf.len().min(self.remaining() / 8);
+        for byte in &mut buf[..count] {
+            *byte = u8::try_from(self.read_bits(8).map_err(io::Error::other)?).expect("Infalible");
+        }
+        Ok(count)
+    }
+}
@@ -0,0 +1,296 @@
+use std::{
+    f64::consts::LN_2,
+    hash::{BuildHasher, BuildHasherDefault, Hash, Hasher},
+    ops::{BitAndAssign, BitOrAssign},
+};
+
+use crate::{random, Blong, FlagLs};
+/// A 64 bit FNV-1a hasher, finished with the `SplitMix64` mixer so every bit of the hash depends on every input byte
+///
+/// Unlike `std`'s `DefaultHasher`, whose algorithm may change between Rust releases, the algorithm is fixed,
+/// and integers are always written little endian with `usize` and `isize` widened to 64 bits
+/// # Portability
+/// The hasher only sees the bytes an item's [`Hash`] impl writes. Integers, `str`, `String` and slices of them write the same bytes on every platform,
+/// but the standard library does not promise this for every type, so a saved filter of any other type may give false negatives when loaded by a program built with a different toolchain
+/// # Examples
+/// ```
+/// use std::hash::{BuildHasher, Hasher};
+/// use packed_flags::{DefaultBuildHasher, FnvHasher};
+///
+/// let mut hasher=FnvHasher::default();
+/// hasher.write(b"a");
+/// assert_eq!(hasher.finish(),0x02c0_bdbf_4814_20f8);
+/// assert_eq!(DefaultBuildHasher::default().hash_one(7_u32),DefaultBuildHasher::default().hash_one(7_u32));
+/// ```
+#[derive(Clone, Copy, Debug)]
+pub struct FnvHasher {
+    state: u64,
+}
+impl Default for FnvHasher {
+    fn default() -> Self {
+        Self { state: 0xCBF2_9CE4_8422_2325 }
+    }
+}
+impl Hasher for FnvHasher {
+    fn write(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.state = (self.state ^ u64::from(*byte)).wrapping_mul(0x0100_0000_01B3);
+        }
+    }
+    fn write_u16(&mut self, i: u16) {
+        self.write(&i.to_le_bytes());
+    }
+    fn write_u32(&mut self, i: u32) {
+        self.write(&i.to_le_bytes());
+    }
+    fn write_u64(&mut self, i: u64) {
+        self.write(&i.to_le_bytes());
+    }
+    fn write_u128(&mut self, i: u128) {
+        self.write(&i.to_le_bytes());
+    }
+    fn write_usize(&mut self, i: usize) {
+        self.write_u64(u64::try_from(i).expect("Infalible"));
+    }
+    fn finish(&self) -> u64 {
+        random::mix(self.state)
+    }
+}
+/// The hasher used by filters when none is given
+///
+/// It uses [`FnvHasher`], so filters built separately can be combined, and a filter saved with serde gives the same answers when it is loaded again,
+/// within the limits described in [`FnvHasher`]'s portability notes
+pub type DefaultBuildHasher = BuildHasherDefault<FnvHasher>;
+
+/// Work out a bit count and hash count for a filter holding `items` items with a false positive rate of `rate`
+#[allow(clippy::cast_precision_loss, clippy::cast_sign_loss, clippy::cast_possible_truncation)]
+fn optimal_size(items: usize, rate: f64) -> (usize, u32) {
+    assert!(rate > 0.0 && rate < 1.0, "False positive rate must be between 0 and 1, got {rate}");
+    let items = items.max(1) as f64;
+    let bits = (-items * rate.ln() / (LN_2 * LN_2)).ceil().max(1.0);
+    let hash_count = (bits / items * LN_2).round().max(1.0);
+    (bits as usize, hash_count as u32)
+}
+/// Get the `hash_count` bit positions an item maps to in a filter of `bits` bits, using double hashing
+fn positions<T: Hash + ?Sized, S: BuildHasher>(hasher: &S, item: &T, bits: usize, hash_count: u32) -> impl Iterator<Item = usize> {
+    let hash = hasher.hash_one(item);
+    let step = hash.rotate_left(32).wrapping_mul(0x9E37_79B9_7F4A_7C15) | 1;
+    let bits = u64::try_from(bits).expect("Infalible");
+    (0..u64::from(hash_count)).map(move |i| {
+        usize::try_from(hash.wrapping_add(i.wrapping_mul(step)) % bits).expect("Infalible")
+    })
+}
+/// A probabilistic set, which can say an item is definitely not present, or that it probably is
+///
+/// The flags are stored in a [`Blong`]. Filters with the same size, hash count and hasher can be combined with
+/// [`union_with`][BloomFilter::union_with] and [`intersect_with`][BloomFilter::intersect_with]
+/// # Examples
+/// ```
+/// use packed_flags::BloomFilter;
+///
+/// let mut filter=BloomFilter::with_rate(100,0.01);
+/// filter.insert("apple");
+/// filter.insert("banana");
+/// assert!(filter.contains("apple"));
+/// assert!(filter.contains("banana"));
+/// ```
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct BloomFilter<S = DefaultBuildHasher> {
+    flags: Blong,
+    hash_count: u32,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    hasher: S,
+}
+impl BloomFilter {
+    /// Create an empty filter with the given number of bits and hashes per item
+    /// # Panics
+    /// If `bits` or `hash_count` is zero
+    #[must_use]
+    pub fn new(bits: usize, hash_count: u32) -> Self {
+        Self::with_hasher(bits, hash_count, DefaultBuildHasher::default())
+    }
+    /// Create an empty filter sized to hold `items` items with a false positive rate of about `rate`
+    /// # Panics
+    /// If `rate` is not strictly between 0 and 1
+    #[must_use]
+    pub fn with_rate(items: usize, rate: f64) -> Self {
+        Self::with_rate_and_hasher(items, rate, DefaultBuildHasher::default())
+    }
+}
+impl<S: BuildHasher> BloomFilter<S> {
+    /// Create an empty filter with the given number of bits, hashes per item, and hasher
+    /// # Panics
+    /// If `bits` or `hash_count` is zero
+    #[must_use]
+    pub fn with_hasher(bits: usize, hash_count: u32, hasher: S) -> Self {
+        assert!(bits > 0 && hash_count > 0, "A bloom filter needs at least one bit and one hash");
+        Self { flags: Blong::all_false(bits), hash_count, hasher }
+    }
+    /// Create an empty filter sized to hold `items` items with a false positive rate of about `rate`, using the given hasher
+    /// # Panics
+    /// If `rate` is not strictly between 0 and 1
+    #[must_use]
+    pub fn with_rate_and_hasher(items: usize, rate: f64, hasher: S) -> Self {
+        let (bits, hash_count) = optimal_size(items, rate);
+        Self::with_hasher(bits, hash_count, hasher)
+    }
+    /// The number of bits in the filter
+    pub fn bit_count(&self) -> usize {
+        self.flags.len()
+    }
+    /// The number of bits set for each item
+    pub const fn hash_count(&self) -> u32 {
+        self.hash_count
+    }
+    /// Get a reference to the underlying flags
+    pub const fn as_flags(&self) -> &Blong {
+        &self.flags
+    }
+    /// Adds an item to the filter, returning false if it was probably already present
+    pub fn insert<T: Hash + ?Sized>(&mut self, item: &T) -> bool {
+        let mut new = false;
+        for idx in positions(&self.hasher, item, self.flags.len(), self.hash_count) {
+            new |= self.flags.try_set(idx, true) == Some(false);
+        }
+        new
+    }
+    /// Returns false if the item is definitely not in the filter, and true if it probably is
+    pub fn contains<T: Hash + ?Sized>(&self, item: &T) -> bool {
+        positions(&self.hasher, item, self.flags.len(), self.hash_count).all(|idx| self.flags.get(idx) == Some(true))
+    }
+    /// Removes every item from the filter
+    pub fn clear(&mut self) {
+        let len = self.flags.len();
+        self.flags.clear();
+        self.flags.set_len(len);
+    }
+    fn assert_compatible(&self, other: &Self) {
+        assert!(
+            self.flags.len() == other.flags.len() && self.hash_count == other.hash_count,
+            "Cannot combine a filter of {} bits and {} hashes with one of {} bits and {} hashes",
+            self.flags.len(), self.hash_count, other.flags.len(), other.hash_count
+        );
+    }
+    /// Adds every item from `other` to this filter
+    ///
+    /// Both filters must use equivalent hashers for the result to be meaningful
+    /// # Panics
+    /// If the filters have a different number of bits or hashes
+    pub fn union_with(&mut self, other: &Self) {
+        self.assert_compatible(other);
+        self.flags.bitor_assign(&other.flags);
+    }
+    /// Keeps only items which might also be in `other`
+    ///
+    /// The result may have a higher false positive rate than a filter built from the common items directly
+    /// # Panics
+    /// If the filters have a different number of bits or hashes
+    pub fn intersect_with(&mut self, other: &Self) {
+        self.assert_compatible(other);
+        self.flags.bitand_assign(&other.flags);
+    }
+}
+/// A bloom filter which counts how many items set each position, so that items can be removed
+///
+/// Each counter is a byte. A counter which reaches 255 is never decremented, so it can not cause false negatives
+/// # Examples
+/// ```
+/// use packed_flags::CountingBloomFilter;
+///
+/// let mut filter=CountingBloomFilter::with_rate(100,0.01);
+/// filter.insert("apple");
+/// assert!(filter.contains("apple"));
+/// assert!(filter.remove("apple"));
+/// assert!(!filter.contains("apple"));
+/// ```
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CountingBloomFilter<S = DefaultBuildHasher> {
+    counters: Vec<u8>,
+    hash_count: u32,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    hasher: S,
+}
+impl CountingBloomFilter {
+    /// Create an empty filter with the given number of counters and hashes per item
+    /// # Panics
+    /// If `counters` or `hash_count` is zero
+    #[must_use]
+    pub fn new(counters: usize, hash_count: u32) -> Self {
+        Self::with_hasher(counters, hash_count, DefaultBuildHasher::default())
+    }
+    /// Create an empty filter sized to hold `items` items with a false positive rate of about `rate`
+    /// # Panics
+    /// If `rate` is not strictly between 0 and 1
+    #[must_use]
+    pub fn with_rate(items: usize, rate: f64) -> Self {
+        Self::with_rate_and_hasher(items, rate, DefaultBuildHasher::default())
+    }
+}
+impl<S: BuildHasher> CountingBloomFilter<S> {
+    /// Create an empty filter with the given number of counters, hashes per item, and hasher
+    /// # Panics
+    /// If `counters` or `hash_count` is zero
+    #[must_use]
+    pub fn with_hasher(counters: usize, hash_count: u32, hasher: S) -> Self {
+        assert!(counters > 0 && hash_count > 0, "A bloom filter needs at least one counter and one hash");
+        Self { counters: vec![0; counters], hash_count, hasher }
+    }
+    /// Create an empty filter sized to hold `items` items with a false positive rate of about `rate`, using the given hasher
+    /// # Panics
+    /// If `rate` is not strictly between 0 and 1
+    #[must_use]
+    pub fn with_rate_and_hasher(items: usize, rate: f64, hasher: S) -> Self {
+        let (counters, hash_count) = optimal_size(items, rate);
+        Self::with_hasher(counters, hash_count, hasher)
+    }
+    /// The number of counters in the filter
+    pub const fn counter_count(&self) -> usize {
+        self.counters.len()
+    }
+    /// The number of counters incremented for each item
+    pub const fn hash_count(&self) -> u32 {
+        self.hash_count
+    }
+    /// Adds an item to the filter
+    pub fn insert<T: Hash + ?Sized>(&mut self, item: &T) {
+        for idx in positions(&self.hasher, item, self.counters.len(), self.hash_count) {
+            self.counters[idx] = self.counters[idx].saturating_add(1);
+        }
+    }
+    /// Returns false if the item is definitely not in the filter, and true if it probably is
+    pub fn contains<T: Hash + ?Sized>(&self, item: &T) -> bool {
+        positions(&self.hasher, item, self.counters.len(), self.hash_count).all(|idx| self.counters[idx] > 0)
+    }
+    /// Removes an item from the filter, returning false and doing nothing if it was definitely not present
+    ///
+    /// Removing an item which was never inserted, but tests as present, can cause false negatives for other items
+    pub fn remove<T: Hash + ?Sized>(&mut self, item: &T) -> bool {
+        if !self.contains(item) {
+            return false;
+        }
+        for idx in positions(&self.hasher, item, self.counters.len(), self.hash_count) {
+            if self.counters[idx] < u8::MAX {
+                self.counters[idx] -= 1;
+            }
+        }
+        true
+    }
+    /// Removes every item from the filter
+    pub fn clear(&mut self) {
+        self.counters.fill(0);
+    }
+    /// Converts into a plain [`BloomFilter`] containing the same items
+    #[must_use]
+    pub fn to_bloom_filter(&self) -> BloomFilter<S>
+    where
+        S: Clone,
+    {
+        BloomFilter {
+            flags: Blong::from_iter(self.counters.iter().map(|count| *count > 0)),
+            hash_count: self.hash_count,
+            hasher: self.hasher.clone(),
+        }
+    }
+}
@@ -0,0 +1,109 @@
+use crate::{flagls::copy_flags, BAuto, Blong, Bsize, FlagLs, B128, B32, B64};
+/// Joins two lists of flags, possibly of different types, into a type wide enough to hold both
+///
+/// Two `B32`s make a `B64`, a `B32` and a `B64` or two `B64`s make a `B128`, and anything else makes a `Blong`
+/// # Examples
+/// ```
+/// use packed_flags::{Blong, Concat, B32, B64};
+/// use packed_flags::FlagLs;
+///
+/// let header=B32::all_true(32);
+/// let payload=B32::from_iter(vec![false,true]);
+/// let mut packet: B64=header.concat(&payload);
+/// assert_eq!(packet.len(),34);
+/// assert_eq!(packet.split_off(32),B64::from_iter(vec![false,true]));
+///
+/// let long: Blong=payload.concat(&Blong::all_true(100));
+/// assert_eq!(long.len(),102);
+/// ```
+pub trait Concat<Rhs: FlagLs = Self>: FlagLs {
+    /// The type of the joined list
+    type Output: FlagLs;
+    /// Returns a list of the flags of `self` followed by the flags of `rhs`
+    #[must_use]
+    fn concat(&self, rhs: &Rhs) -> Self::Output {
+        let mut out = Self::Output::default();
+        out.set_len(self.len() + rhs.len());
+        copy_flags(self, 0, &mut out, 0, self.len());
+        copy_flags(rhs, 0, &mut out, self.len(), rhs.len());
+        out
+    }
+}
+impl Concat<Self> for B32 {
+    type Output = B64;
+}
+impl Concat<B64> for B32 {
+    type Output = B128;
+}
+impl Concat<B128> for B32 {
+    type Output = Blong;
+}
+impl Concat<Bsize> for B32 {
+    type Output = Blong;
+}
+impl Concat<Blong> for B32 {
+    type Output = Blong;
+}
+impl Concat<B32> for B64 {
+    type Output = B128;
+}
+impl Concat<Self> for B64 {
+    type Output = B128;
+}
+impl Concat<B128> for B64 {
+    type Output = Blong;
+}
+impl Concat<Bsize> for B64 {
+    type Output = Blong;
+}
+impl Concat<Blong> for B64 {
+    type Output = Blong;
+}
+impl Concat<B32> for B128 {
+    type Output = Blong;
+}
+impl Concat<B64> for B128 {
+    type Output = Blong;
+}
+impl Concat<Self> for B128 {
+    type Output = Blong;
+}
+impl Concat<Bsize> for B128 {
+    type Output = Blong;
+}
+impl Concat<Blong> for B128 {
+    type Output = Blong;
+}
+impl Concat<B32> for Bsize {
+    type Output = Blong;
+}
+impl Concat<B64> for Bsize {
+    type Output = Blong;
+}
+impl Concat<B128> for Bsize {
+    type Output = Blong;
+}
+impl Concat<Self> for Bsize {
+    type Output = Blong;
+}
+impl Concat<Blong> for Bsize {
+    type Output = Blong;
+}
+impl Concat<B32> for Blong {
+    type Output = Self;
+}
+impl Concat<B64> for Blong {
+    type Output = Self;
+}
+impl Concat<B128> for Blong {
+    type Output = Self;
+}
+impl Concat<Bsize> for Blong {
+    type Output = Self;
+}
+impl Concat<Self> for Blong {
+    type Output = Self;
+}
+impl Concat<Self> for BAuto {
+    type Output = Self;
+}
@@ -0,0 +1,209 @@
+use std::{error::Error, fmt::Display};
+
+use crate::{flag_debug::runs, flagls::fill, FlagLs, FlagLsError};
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+///Represents errors that can occur when parsing a cpulist or cpumask
+///
+///Every variant except `Flags` holds `pos`, the byte offset in the text of the token that caused the error
+pub enum CpuTextError {
+    UnexpectedChar { pos: usize, found: char },
+    MissingNumber { pos: usize },
+    NumberTooLarge { pos: usize },
+    ReversedRange { pos: usize, start: usize, end: usize },
+    BadStride { pos: usize, used: usize, group: usize },
+    GroupTooLong { pos: usize, digits: usize },
+    IndexTooLarge { pos: usize, idx: usize, mx_len: usize },
+    Flags(FlagLsError),
+}
+impl CpuTextError {
+    /// The byte offset in the text of the token that caused the error, or `None` if the error is not tied to a token
+    /// # Examples
+    /// ```
+    /// use packed_flags::{B32, CpuTextError};
+    /// use packed_flags::FlagLs;
+    ///
+    /// assert_eq!(B32::from_cpulist("0-3,9-x").map_err(|err| err.pos()),Err(Some(6)));
+    /// assert_eq!(B32::from_cpulist("0-3,40").map_err(|err| err.pos()),Err(Some(4)));
+    /// ```
+    #[must_use]
+    pub const fn pos(&self) -> Option<usize> {
+        match self {
+            Self::UnexpectedChar { pos, .. }
+            | Self::MissingNumber { pos }
+            | Self::NumberTooLarge { pos }
+            | Self::ReversedRange { pos, .. }
+            | Self::BadStride { pos, .. }
+            | Self::GroupTooLong { pos, .. }
+            | Self::IndexTooLarge { pos, .. } => Some(*pos),
+            Self::Flags(_) => None,
+        }
+    }
+}
+impl Error for CpuTextError {}
+impl Display for CpuTextError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self {
+            Self::UnexpectedChar { pos, found } => write!(f, "unexpected character {found:?} at position {pos}"),
+            Self::MissingNumber { pos } => write!(f, "expected a number at position {pos}"),
+            Self::NumberTooLarge { pos } => write!(f, "number at position {pos} does not fit in a usize"),
+            Self::ReversedRange { pos, start, end } => write!(f, "range {start}-{end} at position {pos} ends before it starts"),
+            Self::BadStride { pos, used, group } => write!(f, "stride {used}/{group} at position {pos} must use between 1 and {group} flags of each group"),
+            Self::GroupTooLong { pos, digits } => write!(f, "mask group at position {pos} has {digits} hex digits, groups have at most 8"),
+            Self::IndexTooLarge { pos, idx, mx_len } => write!(f, "flag {idx} at position {pos} does not fit in a flag list with maximum length {mx_len}"),
+            Self::Flags(err) => write!(f, "{err}"),
+        }
+    }
+}
+impl From<FlagLsError> for CpuTextError {
+    fn from(value: FlagLsError) -> Self {
+        Self::Flags(value)
+    }
+}
+/// One `start-end:used/group` token of a cpulist, covering `used` flags at the start of every `group` flags from `start` to `end`
+struct Span {
+    start: usize,
+    end: usize,
+    used: usize,
+    group: usize,
+}
+impl Span {
+    /// The index of the last flag the token sets
+    const fn last(&self) -> usize {
+        let group_start = self.start + (self.end - self.start) / self.group * self.group;
+        let last_used = group_start.saturating_add(self.used - 1);
+        if last_used < self.end { last_used } else { self.end }
+    }
+}
+/// Strips surrounding whitespace from `text`, returning what is left and its byte offset in `text`
+fn trimmed(text: &str) -> (usize, &str) {
+    let start = text.trim_start();
+    (text.len() - start.len(), start.trim_end())
+}
+/// Splits `text` at every `sep`, pairing each part with its byte offset, counting from `base`
+fn split_offsets(text: &str, base: usize, sep: char) -> impl Iterator<Item = (usize, &str)> {
+    text.split(sep).scan(base, move |offset, part| {
+        let pos = *offset;
+        *offset += part.len() + sep.len_utf8();
+        Some((pos, part))
+    })
+}
+/// Parses a decimal number found at byte offset `pos`
+fn number(text: &str, pos: usize) -> Result<usize, CpuTextError> {
+    if let Some((idx, found)) = text.char_indices().find(|(_, ch)| !ch.is_ascii_digit()) {
+        return Err(CpuTextError::UnexpectedChar { pos: pos + idx, found });
+    }
+    if text.is_empty() {
+        return Err(CpuTextError::MissingNumber { pos });
+    }
+    text.parse().map_err(|_| CpuTextError::NumberTooLarge { pos })
+}
+fn span(token: &str, pos: usize) -> Result<Span, CpuTextError> {
+    let (range, stride) = token.split_once(':').map_or((token, None), |(range, stride)| (range, Some(stride)));
+    let (start, end) = if let Some((start, end)) = range.split_once('-') {
+        (number(start, pos)?, number(end, pos + start.len() + 1)?)
+    } else {
+        let idx = number(range, pos)?;
+        (idx, idx)
+    };
+    if start > end {
+        return Err(CpuTextError::ReversedRange { pos, start, end });
+    }
+    let (used, group) = match stride {
+        Some(stride) => {
+            let stride_pos = pos + range.len() + 1;
+            let (used, group) = stride.split_once('/').ok_or(CpuTextError::MissingNumber { pos: stride_pos + stride.len() })?;
+            let (used, group) = (number(used, stride_pos)?, number(group, stride_pos + used.len() + 1)?);
+            if used == 0 || used > group {
+                return Err(CpuTextError::BadStride { pos: stride_pos, used, group });
+            }
+            (used, group)
+        }
+        None => (1, 1),
+    };
+    Ok(Span { start, end, used, group })
+}
+pub fn parse_cpulist<T: FlagLs>(text: &str) -> Result<T, CpuTextError> {
+    let (base, body) = trimmed(text);
+    let mut spans = Vec::new();
+    let mut len = 0;
+    if !body.is_empty() {
+        for (pos, token) in split_offsets(body, base, ',') {
+            let span = span(token, pos)?;
+            let last = span.last();
+            if last >= T::MAX_LENGTH {
+                return Err(CpuTextError::IndexTooLarge { pos, idx: last, mx_len: T::MAX_LENGTH });
+            }
+            len = len.max(last + 1);
+            spans.push(span);
+        }
+    }
+    let mut out = T::default();
+    out.try_set_len(len)?;
+    for span in spans {
+        if span.used == span.group {
+            fill(&mut out, span.start..span.end + 1);
+            continue;
+        }
+        for group_start in (span.start..=span.end).step_by(span.group) {
+            fill(&mut out, group_start..span.end.min(group_start.saturating_add(span.used - 1)) + 1);
+        }
+    }
+    Ok(out)
+}
+pub fn format_cpulist<T: FlagLs>(flags: &T) -> String {
+    runs(flags)
+        .iter()
+        .map(|run| if run.len() == 1 { run.start.to_string() } else { format!("{}-{}", run.start, run.end - 1) })
+        .collect::<Vec<_>>()
+        .join(",")
+}
+pub fn parse_cpumask<T: FlagLs>(text: &str) -> Result<T, CpuTextError> {
+    let (base, body) = trimmed(text);
+    let (base, body) = body.strip_prefix("0x").or_else(|| body.strip_prefix("0X")).map_or((base, body), |rest| (base + 2, rest));
+    if body.is_empty() {
+        return Ok(T::default());
+    }
+    let groups: Vec<(usize, &str)> = split_offsets(body, base, ',').collect();
+    // (byte offset, index of the 4 flags it covers, value) for each hex digit
+    let mut digits = Vec::with_capacity(body.len());
+    for (idx, (pos, group)) in groups.iter().enumerate() {
+        if group.is_empty() {
+            return Err(CpuTextError::MissingNumber { pos: *pos });
+        }
+        if group.len() > 8 {
+            return Err(CpuTextError::GroupTooLong { pos: *pos, digits: group.len() });
+        }
+        for (offset, found) in group.char_indices() {
+            let value = found.to_digit(16).ok_or(CpuTextError::UnexpectedChar { pos: pos + offset, found })?;
+            digits.push((pos + offset, 8 * (groups.len() - 1 - idx) + group.len() - 1 - offset, value));
+        }
+    }
+    for &(pos, nibble, value) in digits.iter().filter(|(_, _, value)| *value != 0) {
+        let idx = nibble * 4 + usize::try_from(value.ilog2()).expect("Infalible");
+        if idx >= T::MAX_LENGTH {
+            return Err(CpuTextError::IndexTooLarge { pos, idx, mx_len: T::MAX_LENGTH });
+        }
+    }
+    let len = (4 * groups[0].1.len() + 32 * (groups.len() - 1)).min(T::MAX_LENGTH);
+    let mut out = T::default();
+    out.try_set_len(len)?;
+    for (_, nibble, value) in digits {
+        if value != 0 {
+            out.set_bits(nibble * 4..len.min(nibble * 4 + 4), value.into());
+        }
+    }
+    Ok(out)
+}
+pub fn format_cpumask<T: FlagLs>(flags: &T) -> String {
+    let len = flags.len();
+    let nibbles = len.div_ceil(4);
+    let mut out = String::with_capacity(nibbles + nibbles / 8);
+    for nibble in (0..nibbles).rev() {
+        let value = u32::try_from(flags.get_bits(nibble * 4..len.min(nibble * 4 + 4))).expect("Infalible");
+        out.push(char::from_digit(value, 16).expect("Infalible"));
+        if nibble % 8 == 0 && nibble > 0 {
+            out.push(',');
+        }
+    }
+    out
+}
@@ -0,0 +1,81 @@
+use std::{
+    fmt::{self, Debug, Formatter, Write},
+    ops::Range,
+};
+
+use crate::FlagLs;
+
+/// Lists longer than this only show their first and last `SHOWN/2` flags
+const SHOWN: usize = 128;
+/// Lists with more runs of true flags than this only list their first and last `RUNS/2` runs
+const RUNS: usize = 64;
+
+/// The runs of consecutive true flags, as ranges of indices
+pub fn runs<T: FlagLs>(flag_ls: &T) -> Vec<Range<usize>> {
+    let mut out: Vec<Range<usize>> = Vec::new();
+    for (idx, flag) in flag_ls.iter().enumerate() {
+        match out.last_mut() {
+            Some(run) if flag && run.end == idx => run.end += 1,
+            _ if flag => out.push(idx..idx + 1),
+            _ => {}
+        }
+    }
+    out
+}
+/// Writes the flags in `range` as a string of `0`s and `1`s
+fn write_bits<T: FlagLs>(flag_ls: &T, mut range: Range<usize>, f: &mut Formatter<'_>) -> fmt::Result {
+    range.try_for_each(|idx| f.write_char(if flag_ls.get(idx) == Some(true) { '1' } else { '0' }))
+}
+/// The runs of true flags, written inline as `[0..=3, 7, 10..=12]` even in alternate mode
+struct SetRuns(Vec<Range<usize>>);
+impl Debug for SetRuns {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let write_run = |run: &Range<usize>, f: &mut Formatter<'_>| {
+            if run.len() == 1 {
+                write!(f, "{}", run.start)
+            } else {
+                write!(f, "{}..={}", run.start, run.end - 1)
+            }
+        };
+        let (head, tail) = if self.0.len() > RUNS { (&self.0[..RUNS / 2], &self.0[self.0.len() - RUNS / 2..]) } else { (&self.0[..], &[][..]) };
+        f.write_char('[')?;
+        for (idx, run) in head.iter().enumerate() {
+            if idx > 0 {
+                f.write_str(", ")?;
+            }
+            write_run(run, f)?;
+        }
+        if !tail.is_empty() {
+            write!(f, ", …({} more runs)…", self.0.len() - RUNS)?;
+            for run in tail {
+                f.write_str(", ")?;
+                write_run(run, f)?;
+            }
+        }
+        f.write_char(']')
+    }
+}
+/// Writes a list of flags as `Name[0101](len=4)`, or with `{:#?}` as its length, number of true flags and the runs of true flags
+///
+/// Only the first and last flags of long lists are written, with a count of the flags and true flags between them
+pub fn fmt_flags<T: FlagLs>(flag_ls: &T, name: &str, f: &mut Formatter<'_>) -> fmt::Result {
+    let len = flag_ls.len();
+    if f.alternate() {
+        return f.debug_struct(name).field("len", &len).field("ones", &flag_ls.and_count(flag_ls)).field("set", &SetRuns(runs(flag_ls))).finish();
+    }
+    write!(f, "{name}[")?;
+    write_flags(flag_ls, f)?;
+    write!(f, "](len={len})")
+}
+/// Writes the flags as a string of `0`s and `1`s, with only the first and last flags of long lists
+pub fn write_flags<T: FlagLs>(flag_ls: &T, f: &mut Formatter<'_>) -> fmt::Result {
+    let len = flag_ls.len();
+    if len <= SHOWN {
+        return write_bits(flag_ls, 0..len, f);
+    }
+    let (head, tail) = (0..SHOWN / 2, len - SHOWN / 2..len);
+    let shown_ones = head.clone().chain(tail.clone()).filter(|idx| flag_ls.get(*idx) == Some(true)).count();
+    write_bits(flag_ls, head, f)?;
+    write!(f, "…({} more, {} set)…", len - SHOWN, flag_ls.and_count(flag_ls) - shown_ones)?;
+    write_bits(flag_ls, tail, f)
+}
@@ -10,7 +10,7 @@ where
     T: FlagLs,
 {
     /// Create a new Iterator referencing a flag list
-    pub fn new(ls: &T) -> Iter<T> {
+    pub fn new(ls: &T) -> Iter<'_, T> {
         Iter { inner: ls, front: 0,back: ls.len() }
     }
 }
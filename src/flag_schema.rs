@@ -0,0 +1,208 @@
+use std::{collections::HashMap, error::Error, fmt::Display};
+
+use crate::{FlagLs, FlagLsError};
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+///Represents errors that can occur when building or using a [`FlagSchema`]
+pub enum SchemaError {
+    UnknownName { name: String },
+    DuplicateName { name: String },
+    InvalidName { name: String },
+    Flags(FlagLsError),
+}
+impl Error for SchemaError {}
+impl Display for SchemaError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self {
+            Self::UnknownName { name } => write!(f, "unknown flag name `{name}`"),
+            Self::DuplicateName { name } => write!(f, "flag name `{name}` is already registered"),
+            Self::InvalidName { name } => write!(f, "`{name}` is not a valid flag name, names must be non-empty, may not start with `+` or `-`, and may not contain `,` or whitespace"),
+            Self::Flags(err) => write!(f, "{err}"),
+        }
+    }
+}
+impl From<FlagLsError> for SchemaError {
+    fn from(value: FlagLsError) -> Self {
+        Self::Flags(value)
+    }
+}
+/// A registry of names for flags, built at runtime
+///
+/// Each name refers to one or more flag indices. Flags are registered with [`add_flag`][FlagSchema::add_flag],
+/// and can then be given extra names with [`add_alias`][FlagSchema::add_alias] or bundled together with [`add_group`][FlagSchema::add_group].
+///
+/// Lists of names are written separated by commas, and a name can be prefixed with `-` to clear its flags instead of setting them
+/// # Examples
+/// ```
+/// # use std::error::Error;
+/// #
+/// # fn main() -> Result<(), Box<dyn Error>> {
+/// use packed_flags::{FlagSchema, B32};
+/// use packed_flags::FlagLs;
+///
+/// let mut schema=FlagSchema::new();
+/// schema.add_flag("fast_path",0)?;
+/// schema.add_flag("metrics",1)?;
+/// schema.add_flag("legacy",2)?;
+/// schema.add_group("all",&["fast_path","metrics","legacy"])?;
+///
+/// let flags: B32=schema.parse("all,-legacy")?;
+/// assert_eq!(flags,B32::from_iter(vec![true,true,false]));
+/// assert_eq!(schema.format(&flags),"fast_path,metrics");
+/// assert!(schema.parse::<B32>("fast_path,turbo").is_err());
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, PartialEq, Eq, Debug, Default)]
+pub struct FlagSchema {
+    names: HashMap<String, Vec<usize>>,
+    canonical: Vec<Option<String>>,
+}
+impl FlagSchema {
+    #[must_use]
+    /// Create a new schema with no names registered
+    pub fn new() -> Self {
+        Self::default()
+    }
+    fn check_name(&self, name: &str) -> Result<(), SchemaError> {
+        if name.is_empty()
+            || name.starts_with(['-', '+'])
+            || name.contains(|c: char| c == ',' || c.is_whitespace())
+        {
+            Err(SchemaError::InvalidName { name: name.to_owned() })
+        } else if self.names.contains_key(name) {
+            Err(SchemaError::DuplicateName { name: name.to_owned() })
+        } else {
+            Ok(())
+        }
+    }
+    /// Registers `name` as the name of the flag at `index`
+    ///
+    /// This becomes the name used for that flag by [`format`][FlagSchema::format]
+    /// # Errors
+    /// Errors if the name is invalid, already in use, or if `index` already has a name
+    pub fn add_flag(&mut self, name: &str, index: usize) -> Result<(), SchemaError> {
+        self.check_name(name)?;
+        if let Some(Some(existing)) = self.canonical.get(index) {
+            return Err(SchemaError::DuplicateName { name: existing.clone() });
+        }
+        if self.canonical.len() <= index {
+            self.canonical.resize(index + 1, None);
+        }
+        self.canonical[index] = Some(name.to_owned());
+        self.names.insert(name.to_owned(), vec![index]);
+        Ok(())
+    }
+    /// Registers `alias` as another name for whatever `target` refers to
+    /// # Errors
+    /// Errors if the alias is invalid or already in use, or if `target` is not a known name
+    pub fn add_alias(&mut self, alias: &str, target: &str) -> Result<(), SchemaError> {
+        self.check_name(alias)?;
+        let indices = self.lookup(target).ok_or_else(|| SchemaError::UnknownName { name: target.to_owned() })?.to_vec();
+        self.names.insert(alias.to_owned(), indices);
+        Ok(())
+    }
+    /// Registers `name` as a group which refers to every flag referred to by `members`
+    /// # Errors
+    /// Errors if the name is invalid or already in use, or if any member is not a known name
+    pub fn add_group(&mut self, name: &str, members: &[&str]) -> Result<(), SchemaError> {
+        self.check_name(name)?;
+        let mut indices = Vec::new();
+        for member in members {
+            let member_indices = self.lookup(member).ok_or_else(|| SchemaError::UnknownName { name: (*member).to_owned() })?;
+            indices.extend_from_slice(member_indices);
+        }
+        indices.sort_unstable();
+        indices.dedup();
+        self.names.insert(name.to_owned(), indices);
+        Ok(())
+    }
+    /// Get the flag indices a name refers to, if it is registered
+    #[must_use]
+    pub fn lookup(&self, name: &str) -> Option<&[usize]> {
+        self.names.get(name).map(Vec::as_slice)
+    }
+    /// The length of flag list needed to hold every registered flag
+    #[must_use]
+    pub const fn len(&self) -> usize {
+        self.canonical.len()
+    }
+    /// Returns true when no flags are registered
+    #[must_use]
+    pub const fn is_empty(&self) -> bool {
+        self.canonical.is_empty()
+    }
+    /// Applies a comma separated list of names to an existing list of flags
+    ///
+    /// Names are applied in order, setting their flags, or clearing them if prefixed with `-`. A leading `+` is allowed and ignored.
+    /// The list of flags is extended with false if it is too short to hold every registered flag.
+    /// # Errors
+    /// Errors if a name is not registered, or the list of flags cannot hold every registered flag.
+    /// The flags are left unchanged on error
+    /// # Examples
+    /// ```
+    /// # use std::error::Error;
+    /// #
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// use packed_flags::{FlagSchema, B64};
+    /// use packed_flags::FlagLs;
+    ///
+    /// let mut schema=FlagSchema::new();
+    /// schema.add_flag("metrics",0)?;
+    /// schema.add_flag("legacy",1)?;
+    ///
+    /// let mut flags=B64::all_true(2);
+    /// schema.apply(&mut flags,"-legacy")?;
+    /// assert_eq!(flags,B64::from_iter(vec![true,false]));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn apply<T: FlagLs>(&self, flags: &mut T, names: &str) -> Result<(), SchemaError> {
+        if self.len() > T::MAX_LENGTH {
+            return Err(FlagLsError::MaximumLengthExceeded { mx_len: T::MAX_LENGTH, attempt_len: self.len() }.into());
+        }
+        let mut changes = Vec::new();
+        for token in names.split(',').map(str::trim).filter(|token| !token.is_empty()) {
+            let (flag, name) = token.strip_prefix('-').map_or_else(
+                || (true, token.strip_prefix('+').unwrap_or(token)),
+                |name| (false, name),
+            );
+            let indices = self.lookup(name).ok_or_else(|| SchemaError::UnknownName { name: name.to_owned() })?;
+            changes.push((indices, flag));
+        }
+        while flags.len() < self.len() {
+            flags.push(false);
+        }
+        for (indices, flag) in changes {
+            for idx in indices {
+                flags.set(*idx, flag);
+            }
+        }
+        Ok(())
+    }
+    /// Builds a list of flags from a comma separated list of names
+    ///
+    /// The result is exactly long enough to hold every registered flag. See [`apply`][FlagSchema::apply] for the syntax
+    /// # Errors
+    /// Errors if a name is not registered, or `T` cannot hold every registered flag
+    pub fn parse<T: FlagLs>(&self, names: &str) -> Result<T, SchemaError> {
+        let mut out = T::default();
+        self.apply(&mut out, names)?;
+        Ok(out)
+    }
+    /// Formats the set flags of a list as a comma separated list of names, in index order
+    ///
+    /// Flags registered with [`add_flag`][FlagSchema::add_flag] are written with that name. Set flags with no name are skipped
+    #[must_use]
+    pub fn format<T: FlagLs>(&self, flags: &T) -> String {
+        let mut out = String::new();
+        for (name, flag) in self.canonical.iter().zip(flags.iter()) {
+            if let (Some(name), true) = (name, flag) {
+                if !out.is_empty() {
+                    out.push(',');
+                }
+                out.push_str(name);
+            }
+        }
+        out
+    }
+}
@@ -0,0 +1,189 @@
+use std::{fmt::Debug, hash::{Hash, Hasher}, marker::PhantomData};
+
+use crate::FlagLs;
+/// A type which can be used to index into a list of flags, usually a fieldless enum
+///
+/// This can be implemented by hand, but is normally derived with `#[derive(Flag)]` when the `derive` feature is enabled
+/// # Examples
+/// ```
+/// use packed_flags::{FlagIndex, B32};
+///
+/// #[derive(Clone, Copy, Debug, PartialEq)]
+/// enum Permission { Read, Write }
+///
+/// impl FlagIndex for Permission {
+///     type Backend = B32;
+///     const COUNT: usize = 2;
+///     fn index(&self) -> usize {
+///         *self as usize
+///     }
+///     fn from_index(index: usize) -> Option<Self> {
+///         [Self::Read, Self::Write].get(index).copied()
+///     }
+///     fn name(&self) -> &'static str {
+///         match self {
+///             Self::Read => "Read",
+///             Self::Write => "Write",
+///         }
+///     }
+/// }
+/// assert_eq!(Permission::from_index(Permission::Write.index()), Some(Permission::Write));
+/// ```
+pub trait FlagIndex: Sized {
+    /// The smallest flag list which can hold a flag for every value
+    type Backend: FlagLs;
+    /// The number of distinct values, every index is less than this
+    const COUNT: usize;
+    /// The position of this value's flag
+    fn index(&self) -> usize;
+    /// Get the value whose flag is at `index`, if there is one
+    fn from_index(index: usize) -> Option<Self>;
+    /// A human readable name for this value, used for debug output
+    fn name(&self) -> &'static str;
+}
+/// A set of values of `E`, stored as one flag per value in a `B`
+///
+/// `B` defaults to the smallest backend which fits every value of `E`
+/// # Examples
+/// ```
+/// # #[cfg(feature = "derive")]
+/// # {
+/// use packed_flags::{Flag, FlagSet};
+///
+/// #[derive(Flag, Debug, PartialEq)]
+/// enum Permission { Read, Write, Execute }
+///
+/// let mut perms: FlagSet<Permission> = FlagSet::new();
+/// perms.insert(Permission::Read);
+/// perms.insert(Permission::Execute);
+/// assert!(perms.contains(Permission::Read));
+/// assert!(!perms.contains(Permission::Write));
+/// assert_eq!(perms.iter().collect::<Vec<_>>(), vec![Permission::Read, Permission::Execute]);
+/// assert_eq!(format!("{perms:?}"), "{Read, Execute}");
+/// # }
+/// ```
+pub struct FlagSet<E: FlagIndex, B: FlagLs = <E as FlagIndex>::Backend> {
+    inner: B,
+    marker: PhantomData<fn() -> E>,
+}
+impl<E: FlagIndex, B: FlagLs> FlagSet<E, B> {
+    /// Create an empty set
+    /// # Panics
+    /// Panics if `B` cannot hold `E::COUNT` flags
+    #[must_use]
+    pub fn new() -> Self {
+        Self { inner: B::all_false(E::COUNT), marker: PhantomData }
+    }
+    /// Create a set containing every value of `E`
+    /// # Panics
+    /// Panics if `B` cannot hold `E::COUNT` flags
+    #[must_use]
+    pub fn all() -> Self {
+        Self { inner: B::all_true(E::COUNT), marker: PhantomData }
+    }
+    /// Adds a value to the set, returning true if it was not already present
+    #[allow(clippy::needless_pass_by_value)]
+    pub fn insert(&mut self, value: E) -> bool {
+        self.inner.try_set(value.index(), true) == Some(false)
+    }
+    /// Removes a value from the set, returning true if it was present
+    #[allow(clippy::needless_pass_by_value)]
+    pub fn remove(&mut self, value: E) -> bool {
+        self.inner.try_set(value.index(), false) == Some(true)
+    }
+    /// Returns true if the value is in the set
+    #[allow(clippy::needless_pass_by_value)]
+    pub fn contains(&self, value: E) -> bool {
+        self.inner.get(value.index()) == Some(true)
+    }
+    /// The number of values in the set
+    pub fn len(&self) -> usize {
+        self.inner.and_count(&self.inner)
+    }
+    /// Returns true if the set contains no values
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+    /// Removes every value from the set
+    pub fn clear(&mut self) {
+        self.inner = B::all_false(E::COUNT);
+    }
+    /// Get an iterator over the values in the set, in index order
+    pub fn iter(&self) -> Iter<'_, E, B> {
+        Iter { inner: self.inner.iter().enumerate(), marker: PhantomData }
+    }
+    /// Get a reference to the underlying list of flags
+    pub const fn as_flags(&self) -> &B {
+        &self.inner
+    }
+    /// Converts the set into the underlying list of flags, consuming it
+    #[must_use]
+    pub fn into_flags(self) -> B {
+        self.inner
+    }
+}
+impl<E: FlagIndex, B: FlagLs> Default for FlagSet<E, B> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+impl<E: FlagIndex, B: FlagLs + Clone> Clone for FlagSet<E, B> {
+    fn clone(&self) -> Self {
+        Self { inner: self.inner.clone(), marker: PhantomData }
+    }
+}
+impl<E: FlagIndex, B: FlagLs + Copy> Copy for FlagSet<E, B> {}
+impl<E: FlagIndex, B: FlagLs + PartialEq> PartialEq for FlagSet<E, B> {
+    fn eq(&self, other: &Self) -> bool {
+        self.inner == other.inner
+    }
+}
+impl<E: FlagIndex, B: FlagLs + Eq> Eq for FlagSet<E, B> {}
+impl<E: FlagIndex, B: FlagLs + Hash> Hash for FlagSet<E, B> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.inner.hash(state);
+    }
+}
+impl<E: FlagIndex, B: FlagLs> Debug for FlagSet<E, B> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        struct Name(&'static str);
+        impl Debug for Name {
+            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                f.write_str(self.0)
+            }
+        }
+        f.debug_set().entries(self.iter().map(|value| Name(value.name()))).finish()
+    }
+}
+impl<E: FlagIndex, B: FlagLs> FromIterator<E> for FlagSet<E, B> {
+    fn from_iter<I: IntoIterator<Item = E>>(iter: I) -> Self {
+        let mut out = Self::new();
+        out.extend(iter);
+        out
+    }
+}
+impl<E: FlagIndex, B: FlagLs> Extend<E> for FlagSet<E, B> {
+    fn extend<I: IntoIterator<Item = E>>(&mut self, iter: I) {
+        for value in iter {
+            self.insert(value);
+        }
+    }
+}
+impl<'a, E: FlagIndex, B: FlagLs> IntoIterator for &'a FlagSet<E, B> {
+    type Item = E;
+    type IntoIter = Iter<'a, E, B>;
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+/// An iterator over the values in a [`FlagSet`]
+pub struct Iter<'a, E: FlagIndex, B: FlagLs> {
+    inner: std::iter::Enumerate<crate::flag_iter::Iter<'a, B>>,
+    marker: PhantomData<fn() -> E>,
+}
+impl<E: FlagIndex, B: FlagLs> Iterator for Iter<'_, E, B> {
+    type Item = E;
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.by_ref().find(|(_, flag)| *flag).and_then(|(idx, _)| E::from_index(idx))
+    }
+}
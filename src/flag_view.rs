@@ -0,0 +1,291 @@
+use crate::{Blong, FlagLs, FlagLsError};
+/// Which end of each word the flags start from
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Default)]
+pub enum BitOrder {
+    /// Flag 0 is the least significant bit of the first word, which is the order every other list of flags uses
+    #[default]
+    Lsb0,
+    /// Flag 0 is the most significant bit of the first word, the usual order for bitmaps in network protocols
+    Msb0,
+}
+impl BitOrder {
+    /// The position within a word of `width` bits of the flag at `offset`
+    const fn shift(self, offset: usize, width: usize) -> usize {
+        match self {
+            Self::Lsb0 => offset,
+            Self::Msb0 => width - 1 - offset,
+        }
+    }
+}
+#[derive(Clone, Copy, Debug)]
+enum Words<'a> {
+    U8(&'a [u8]),
+    U32(&'a [u32]),
+    U64(&'a [u64]),
+    Usize(&'a [usize]),
+}
+#[derive(Debug)]
+enum WordsMut<'a> {
+    U8(&'a mut [u8]),
+    U32(&'a mut [u32]),
+    U64(&'a mut [u64]),
+}
+/// Check a buffer of `words` words of `width` bits can hold `len` flags
+const fn check_len(words: usize, width: usize, len: usize) -> Result<(), FlagLsError> {
+    let mx_len = words.saturating_mul(width);
+    if len > mx_len {
+        Err(FlagLsError::MaximumLengthExceeded { mx_len, attempt_len: len })
+    } else {
+        Ok(())
+    }
+}
+/// A read only list of flags borrowed from a buffer of bytes or words, without copying it
+///
+/// Any bits in the buffer past the length of the view are ignored
+/// # Examples
+/// ```
+/// # use std::error::Error;
+/// #
+/// # fn main() -> Result<(), Box<dyn Error>> {
+/// use packed_flags::{BitOrder, FlagView};
+///
+/// let packet=[0b1010_0000_u8,0b0000_0001];
+/// let view=FlagView::from_bytes(&packet,16,BitOrder::Msb0)?;
+/// assert_eq!(view.get(0),Some(true));
+/// assert_eq!(view.get(1),Some(false));
+/// assert_eq!(view.count_ones(),3);
+/// assert_eq!(view.iter_ones().collect::<Vec<_>>(),vec![0,2,15]);
+///
+/// let view=FlagView::from_bytes(&packet,10,BitOrder::Lsb0)?;
+/// assert_eq!(view.iter_ones().collect::<Vec<_>>(),vec![5,7,8]);
+/// assert!(FlagView::from_bytes(&packet,17,BitOrder::Lsb0).is_err());
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Copy, Debug)]
+pub struct FlagView<'a> {
+    words: Words<'a>,
+    len: usize,
+    order: BitOrder,
+}
+impl<'a> FlagView<'a> {
+    /// View the first `len` flags of a byte buffer
+    /// # Errors
+    /// Errors if the buffer holds fewer than `len` bits
+    pub fn from_bytes(bytes: &'a [u8], len: usize, order: BitOrder) -> Result<Self, FlagLsError> {
+        check_len(bytes.len(), 8, len)?;
+        Ok(Self { words: Words::U8(bytes), len, order })
+    }
+    /// View the first `len` flags of a buffer of `u32`s
+    /// # Errors
+    /// Errors if the buffer holds fewer than `len` bits
+    pub fn from_u32s(words: &'a [u32], len: usize, order: BitOrder) -> Result<Self, FlagLsError> {
+        check_len(words.len(), 32, len)?;
+        Ok(Self { words: Words::U32(words), len, order })
+    }
+    /// View the first `len` flags of a buffer of `u64`s
+    /// # Errors
+    /// Errors if the buffer holds fewer than `len` bits
+    pub fn from_u64s(words: &'a [u64], len: usize, order: BitOrder) -> Result<Self, FlagLsError> {
+        check_len(words.len(), 64, len)?;
+        Ok(Self { words: Words::U64(words), len, order })
+    }
+    /// View the first `len` flags of a buffer of `usize`s, the word type [`Blong`] and [`BMatrix`][crate::BMatrix] use
+    /// # Errors
+    /// Errors if the buffer holds fewer than `len` bits
+    pub fn from_usizes(words: &'a [usize], len: usize, order: BitOrder) -> Result<Self, FlagLsError> {
+        check_len(words.len(), usize::BITS as usize, len)?;
+        Ok(Self { words: Words::Usize(words), len, order })
+    }
+    /// Returns the number of flags in the view
+    #[must_use]
+    pub const fn len(&self) -> usize {
+        self.len
+    }
+    /// Returns true when there are no flags in the view
+    #[must_use]
+    pub const fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+    /// The bit order the buffer is read with
+    #[must_use]
+    pub const fn bit_order(&self) -> BitOrder {
+        self.order
+    }
+    const fn width(&self) -> usize {
+        match self.words {
+            Words::U8(_) => 8,
+            Words::U32(_) => 32,
+            Words::U64(_) => 64,
+            Words::Usize(_) => usize::BITS as usize,
+        }
+    }
+    /// The flags in word `idx`, moved so flag `idx*width` is the least significant bit, with any flags past the end cleared
+    fn word(&self, idx: usize) -> u64 {
+        let width = self.width();
+        let raw = match self.words {
+            Words::U8(words) => u64::from(words[idx]),
+            Words::U32(words) => u64::from(words[idx]),
+            Words::U64(words) => words[idx],
+            Words::Usize(words) => u64::try_from(words[idx]).expect("Infalible"),
+        };
+        let word = match self.order {
+            BitOrder::Lsb0 => raw,
+            BitOrder::Msb0 => raw.reverse_bits() >> (64 - width),
+        };
+        let remaining = self.len - idx * width;
+        if remaining < width {
+            word & ((1 << remaining) - 1)
+        } else {
+            word
+        }
+    }
+    /// Returns the specified flag, if it exists
+    #[must_use]
+    pub fn get(&self, index: usize) -> Option<bool> {
+        (index < self.len).then(|| (self.word(index / self.width()) >> (index % self.width())) & 1 == 1)
+    }
+    /// Get an iterator over all flags in the view
+    #[must_use]
+    pub fn iter(&self) -> impl DoubleEndedIterator<Item = bool> + ExactSizeIterator + 'a {
+        let view = *self;
+        (0..self.len).map(move |idx| view.get(idx) == Some(true))
+    }
+    /// Counts the true flags, a word at a time
+    #[must_use]
+    #[allow(clippy::missing_panics_doc)]
+    pub fn count_ones(&self) -> usize {
+        (0..self.len.div_ceil(self.width())).map(|idx| usize::try_from(self.word(idx).count_ones()).expect("Infalible")).sum()
+    }
+    /// Get an iterator over the indices of the true flags, in increasing order, skipping a word at a time
+    #[allow(clippy::missing_panics_doc)]
+    pub fn iter_ones(&self) -> impl Iterator<Item = usize> + 'a {
+        let view = *self;
+        let width = self.width();
+        (0..self.len.div_ceil(width)).flat_map(move |idx| {
+            let mut word = view.word(idx);
+            std::iter::from_fn(move || {
+                (word != 0).then(|| {
+                    let offset = usize::try_from(word.trailing_zeros()).expect("Infalible");
+                    word &= word - 1;
+                    idx * width + offset
+                })
+            })
+        })
+    }
+}
+impl From<FlagView<'_>> for Blong {
+    fn from(value: FlagView<'_>) -> Self {
+        let width = value.width();
+        let mut out = Self::default();
+        out.set_len(value.len);
+        for idx in 0..value.len.div_ceil(width) {
+            let start = idx * width;
+            out.set_bits(start..value.len.min(start + width), u128::from(value.word(idx)));
+        }
+        out
+    }
+}
+/// A list of flags borrowed mutably from a buffer of bytes or words, which writes changes straight through to the buffer
+///
+/// Bits in the buffer past the length of the view are never changed
+/// # Examples
+/// ```
+/// # use std::error::Error;
+/// #
+/// # fn main() -> Result<(), Box<dyn Error>> {
+/// use packed_flags::{BitOrder, FlagViewMut};
+///
+/// let mut words=[0_u32;2];
+/// let mut view=FlagViewMut::from_u32s(&mut words,40,BitOrder::Msb0)?;
+/// view.set(0,true);
+/// view.set(33,true);
+/// assert_eq!(view.try_set(40,true),None);
+/// assert_eq!(view.as_view().iter_ones().collect::<Vec<_>>(),vec![0,33]);
+/// assert_eq!(words,[1<<31,1<<30]);
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct FlagViewMut<'a> {
+    words: WordsMut<'a>,
+    len: usize,
+    order: BitOrder,
+}
+impl<'a> FlagViewMut<'a> {
+    /// Mutably view the first `len` flags of a byte buffer
+    /// # Errors
+    /// Errors if the buffer holds fewer than `len` bits
+    pub fn from_bytes(bytes: &'a mut [u8], len: usize, order: BitOrder) -> Result<Self, FlagLsError> {
+        check_len(bytes.len(), 8, len)?;
+        Ok(Self { words: WordsMut::U8(bytes), len, order })
+    }
+    /// Mutably view the first `len` flags of a buffer of `u32`s
+    /// # Errors
+    /// Errors if the buffer holds fewer than `len` bits
+    pub fn from_u32s(words: &'a mut [u32], len: usize, order: BitOrder) -> Result<Self, FlagLsError> {
+        check_len(words.len(), 32, len)?;
+        Ok(Self { words: WordsMut::U32(words), len, order })
+    }
+    /// Mutably view the first `len` flags of a buffer of `u64`s
+    /// # Errors
+    /// Errors if the buffer holds fewer than `len` bits
+    pub fn from_u64s(words: &'a mut [u64], len: usize, order: BitOrder) -> Result<Self, FlagLsError> {
+        check_len(words.len(), 64, len)?;
+        Ok(Self { words: WordsMut::U64(words), len, order })
+    }
+    /// Reborrow as a read only view, for reading flags
+    #[must_use]
+    pub const fn as_view(&self) -> FlagView<'_> {
+        let words = match &self.words {
+            WordsMut::U8(words) => Words::U8(words),
+            WordsMut::U32(words) => Words::U32(words),
+            WordsMut::U64(words) => Words::U64(words),
+        };
+        FlagView { words, len: self.len, order: self.order }
+    }
+    /// Returns the number of flags in the view
+    #[must_use]
+    pub const fn len(&self) -> usize {
+        self.len
+    }
+    /// Returns true when there are no flags in the view
+    #[must_use]
+    pub const fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+    /// Returns the specified flag, if it exists
+    #[must_use]
+    pub fn get(&self, index: usize) -> Option<bool> {
+        self.as_view().get(index)
+    }
+    /// Counts the true flags, a word at a time
+    #[must_use]
+    pub fn count_ones(&self) -> usize {
+        self.as_view().count_ones()
+    }
+    /// Set the flag at a specified index, in the underlying buffer
+    /// # Panics
+    /// If the index is out of bounds
+    pub fn set(&mut self, index: usize, flag: bool) {
+        assert!(index < self.len, "Cannot set flag {index} of a view of length {}", self.len);
+        match &mut self.words {
+            WordsMut::U8(words) => {
+                let shift = self.order.shift(index % 8, 8);
+                words[index / 8] = (words[index / 8] & !(1 << shift)) | (u8::from(flag) << shift);
+            }
+            WordsMut::U32(words) => {
+                let shift = self.order.shift(index % 32, 32);
+                words[index / 32] = (words[index / 32] & !(1 << shift)) | (u32::from(flag) << shift);
+            }
+            WordsMut::U64(words) => {
+                let shift = self.order.shift(index % 64, 64);
+                words[index / 64] = (words[index / 64] & !(1 << shift)) | (u64::from(flag) << shift);
+            }
+        }
+    }
+    /// Attempts to set the flag at the specified index, returning the flag that was there if the index is in bounds
+    pub fn try_set(&mut self, index: usize, flag: bool) -> Option<bool> {
+        self.get(index).inspect(|_| self.set(index, flag))
+    }
+}
@@ -1,8 +1,78 @@
-use crate::{flag_iter, FlagLsError};
+// the helpers below are shared with the backends, and marked `pub(crate)` so they read as internal even though the module is private
+#![allow(clippy::redundant_pub_crate)]
+use std::{
+    io::{Read, Write},
+    ops::Range,
+};
+
+use crate::{bitmap_file, cpu_text, flag_iter, random, CpuTextError, FlagLsError, FormatError};
+/// Copies `len` flags from `src` starting at `src_start` into `dst` starting at `dst_start`, up to 128 flags at a time
+///
+/// Both ranges must already be in bounds
+pub(crate) fn copy_flags<S: FlagLs, D: FlagLs>(src: &S, src_start: usize, dst: &mut D, dst_start: usize, len: usize) {
+    let mut done = 0;
+    while done < len {
+        let width = (len - done).min(128);
+        dst.set_bits(dst_start + done..dst_start + done + width, src.get_bits(src_start + done..src_start + done + width));
+        done += width;
+    }
+}
+/// Sets every flag in `range`, up to 128 flags at a time
+///
+/// The range must already be in bounds
+pub(crate) fn fill<T: FlagLs>(flags: &mut T, range: Range<usize>) {
+    let mut start = range.start;
+    while start < range.end {
+        let end = range.end.min(start + 128);
+        flags.set_bits(start..end, u128::MAX);
+        start = end;
+    }
+}
+/// Checks `idx` is the index of a flag in a list of length `len`
+pub(crate) const fn check_index(idx: usize, len: usize) -> Result<(), FlagLsError> {
+    if idx < len {
+        Ok(())
+    } else {
+        Err(FlagLsError::IndexOutOfBounds { idx, len })
+    }
+}
+/// Checks a list with maximum length `mx_len` can be `attempt_len` long
+pub(crate) const fn check_len(mx_len: usize, attempt_len: usize) -> Result<(), FlagLsError> {
+    if attempt_len > mx_len {
+        Err(FlagLsError::MaximumLengthExceeded { mx_len, attempt_len })
+    } else {
+        Ok(())
+    }
+}
+/// Checks `range` is a range of flags in a list of length `len`, where a range which starts after it ends is an error
+pub(crate) const fn check_range(range: &Range<usize>, len: usize) -> Result<(), FlagLsError> {
+    if range.start > range.end {
+        Err(FlagLsError::InvalidRange { start: range.start, end: range.end })
+    } else if range.end > len {
+        Err(FlagLsError::IndexOutOfBounds { idx: range.end - 1, len })
+    } else {
+        Ok(())
+    }
+}
+/// Checks a range of flags can be read as a field of a list of length `len`, returning the width of the field
+///
+/// Like other ranges, a range which starts after it ends is empty
+pub(crate) fn field_width(range: &Range<usize>, len: usize) -> Result<usize, FlagLsError> {
+    let width = range.len();
+    if width == 0 {
+        Ok(0)
+    } else if range.end > len {
+        Err(FlagLsError::IndexOutOfBounds { idx: range.end - 1, len })
+    } else if width > 128 {
+        Err(FlagLsError::MaximumLengthExceeded { mx_len: 128, attempt_len: width })
+    } else {
+        Ok(width)
+    }
+}
 /// A trait that represents a list of flags.
 ///
 /// Mostly the same as things that would be implemented by `Vec<bool>` with a few omisions
-/// Types that implement this trait include [`B32`][crate::B32], [`B64`][crate::B64], [`B128`][crate::B128], [`Bsize`][crate::Bsize], and [`Blong`][crate::Blong]
+/// Types that implement this trait include [`B32`][crate::B32], [`B64`][crate::B64], [`B128`][crate::B128], [`Bsize`][crate::Bsize], [`Blong`][crate::Blong], and [`BAuto`][crate::BAuto]
 pub trait FlagLs: Sized+Default
 {
     /// The max length a given flag list can store
@@ -41,6 +111,24 @@ pub trait FlagLs: Sized+Default
     /// assert_eq!(flag_ls,B64::from_iter(vec![true,true,true,true,false,false]));
     /// ```
     fn set_len(&mut self, new_len: usize);
+    /// Attempts to set the length of the flag ls, zeroing any flags removed
+    /// # Errors
+    /// Errors if the new length would be larger than `MAX_LENGTH`, in which case the list is unchanged
+    /// # Examples
+    /// ```
+    /// use packed_flags::{B32, FlagLsError};
+    /// use packed_flags::FlagLs;
+    ///
+    /// let mut flag_ls=B32::all_true(4);
+    /// assert_eq!(flag_ls.try_set_len(6),Ok(()));
+    /// assert_eq!(flag_ls.try_set_len(33),Err(FlagLsError::MaximumLengthExceeded { mx_len: 32, attempt_len: 33 }));
+    /// assert_eq!(flag_ls,B32::from_iter(vec![true,true,true,true,false,false]));
+    /// ```
+    fn try_set_len(&mut self, new_len: usize) -> Result<(), FlagLsError> {
+        check_len(Self::MAX_LENGTH, new_len)?;
+        self.set_len(new_len);
+        Ok(())
+    }
     /// Inserts a new flag at the position given by index(ie so `flag_ls[index]=flag`)
     /// # Panics
     /// Panics if the index is out of bounds or the new element would make the list longer than `MAX_LENGTH`
@@ -58,7 +146,7 @@ pub trait FlagLs: Sized+Default
     fn insert(&mut self, index: usize, flag: bool);
     /// Attempts to insert at index, returning Ok(()) on success
     /// # Errors
-    /// Errors if the index is out of bounds(note idx=self.len() is considered in bounds for insert) or if the insert would bring the list above it's maximum allowed length
+    /// Errors if the index is out of bounds(note `idx=self.len()` is considered in bounds for insert) or if the insert would bring the list above it's maximum allowed length
     /// # Examples  
     /// ```
     /// use packed_flags::B64;
@@ -112,6 +200,22 @@ pub trait FlagLs: Sized+Default
             None
         }
     }
+    /// Removes the flag at the position given by index, and returns it, or an error if the index is out of bounds
+    /// # Errors
+    /// Errors if the index is out of bounds
+    /// # Examples
+    /// ```
+    /// use packed_flags::{B64, FlagLsError};
+    /// use packed_flags::FlagLs;
+    ///
+    /// let mut flag_ls=B64::from_iter(vec![false,true,false]);
+    /// assert_eq!(flag_ls.checked_remove(1),Ok(true));
+    /// assert_eq!(flag_ls.checked_remove(2),Err(FlagLsError::IndexOutOfBounds { idx: 2, len: 2 }));
+    /// ```
+    fn checked_remove(&mut self, index: usize) -> Result<bool, FlagLsError> {
+        check_index(index, self.len())?;
+        Ok(self.remove(index))
+    }
     /// Clears the list, setting the internal state and length to 0
     /// # Examples
     /// ```
@@ -223,6 +327,23 @@ pub trait FlagLs: Sized+Default
     /// assert_eq!(flag_ls.get(2),None);
     /// ```
     fn get(&self, index: usize) -> Option<bool>;
+    /// Get the flag at a specified index, or an error if the index is out of bounds
+    ///
+    /// This is the non-panicking version of indexing, `flag_ls[index]`
+    /// # Errors
+    /// Errors if the index is out of bounds
+    /// # Examples
+    /// ```
+    /// use packed_flags::{B64, FlagLsError};
+    /// use packed_flags::FlagLs;
+    ///
+    /// let flag_ls=B64::from_iter(vec![false,true]);
+    /// assert_eq!(flag_ls.checked_get(1),Ok(true));
+    /// assert_eq!(flag_ls.checked_get(2),Err(FlagLsError::IndexOutOfBounds { idx: 2, len: 2 }));
+    /// ```
+    fn checked_get(&self, index: usize) -> Result<bool, FlagLsError> {
+        self.get(index).ok_or_else(|| FlagLsError::IndexOutOfBounds { idx: index, len: self.len() })
+    }
     /// Set the flag at a specified index
     /// # Panics
     /// If the index is out of bounds
@@ -248,11 +369,534 @@ pub trait FlagLs: Sized+Default
     /// assert_eq!(flag_ls.try_set(3,false),None);
     /// ```
     fn try_set(&mut self, index: usize, flag: bool) -> Option<bool> {
-        self.get(index).map(|b|{
+        self.get(index).inspect(|_|{
             self.set(index,flag);
-            b
         })
     }
+    /// Sets the flag at the specified index, returning the flag that was there, or an error if the index is out of bounds
+    /// # Errors
+    /// Errors if the index is out of bounds
+    /// # Examples
+    /// ```
+    /// use packed_flags::{B64, FlagLsError};
+    /// use packed_flags::FlagLs;
+    ///
+    /// let mut flag_ls=B64::from_iter(vec![false,true,false]);
+    /// assert_eq!(flag_ls.checked_set(2,true),Ok(false));
+    /// assert_eq!(flag_ls.checked_set(3,true),Err(FlagLsError::IndexOutOfBounds { idx: 3, len: 3 }));
+    /// assert_eq!(flag_ls,B64::from_iter(vec![false,true,true]));
+    /// ```
+    fn checked_set(&mut self, index: usize, flag: bool) -> Result<bool, FlagLsError> {
+        let old = self.checked_get(index)?;
+        self.set(index, flag);
+        Ok(old)
+    }
+    /// Reads the flags in `range` as an unsigned integer, with the flag at `range.start` as the least significant bit
+    /// # Panics
+    /// If the range is out of bounds or more than 128 flags wide
+    /// # Examples
+    /// ```
+    /// use packed_flags::B32;
+    /// use packed_flags::FlagLs;
+    ///
+    /// let register=B32::initialize(0b1011_0110_0000,12);
+    /// assert_eq!(register.get_bits(4..10),0b11_0110);
+    /// assert_eq!(register.get_bits(4..4),0);
+    /// ```
+    fn get_bits(&self, range: Range<usize>) -> u128 {
+        self.try_get_bits(range).expect("Cannot read a field out of bounds or wider than 128 bits")
+    }
+    /// Attempts to read the flags in `range` as an unsigned integer, with the flag at `range.start` as the least significant bit
+    /// # Errors
+    /// Errors with [`FlagLsError::IndexOutOfBounds`] if the range goes past the end of the list,
+    /// or [`FlagLsError::MaximumLengthExceeded`] if it is more than 128 flags wide
+    /// # Examples
+    /// ```
+    /// # use std::error::Error;
+    /// #
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// use packed_flags::B64;
+    /// use packed_flags::FlagLs;
+    ///
+    /// let register=B64::initialize(0xABCD,16);
+    /// assert_eq!(register.try_get_bits(4..12)?,0xBC);
+    /// assert!(register.try_get_bits(12..20).is_err());
+    /// # Ok(())
+    /// # }
+    /// ```
+    fn try_get_bits(&self, range: Range<usize>) -> Result<u128, FlagLsError> {
+        field_width(&range, self.len())?;
+        Ok(range.rev().fold(0, |out, idx| (out << 1) | u128::from(self.get(idx) == Some(true))))
+    }
+    /// Writes the low bits of `value` to the flags in `range`, with the flag at `range.start` taking the least significant bit
+    ///
+    /// Bits of `value` which do not fit in the range are ignored
+    /// # Panics
+    /// If the range is out of bounds or more than 128 flags wide
+    /// # Examples
+    /// ```
+    /// use packed_flags::B32;
+    /// use packed_flags::FlagLs;
+    ///
+    /// let mut register=B32::all_false(12);
+    /// register.set_bits(4..10,0b10_0110);
+    /// assert_eq!(register.as_inner(),0b0010_0110_0000);
+    /// register.set_bits(0..4,0xFF);
+    /// assert_eq!(register.as_inner(),0b0010_0110_1111);
+    /// ```
+    fn set_bits(&mut self, range: Range<usize>, value: u128) {
+        self.try_set_bits(range, value).expect("Cannot write a field out of bounds or wider than 128 bits");
+    }
+    /// Attempts to write the low bits of `value` to the flags in `range`, with the flag at `range.start` taking the least significant bit
+    ///
+    /// Bits of `value` which do not fit in the range are ignored
+    /// # Errors
+    /// Errors with [`FlagLsError::IndexOutOfBounds`] if the range goes past the end of the list,
+    /// or [`FlagLsError::MaximumLengthExceeded`] if it is more than 128 flags wide. The list is unchanged on error
+    /// # Examples
+    /// ```
+    /// use packed_flags::B64;
+    /// use packed_flags::FlagLs;
+    ///
+    /// let mut register=B64::all_false(16);
+    /// assert_eq!(register.try_set_bits(8..16,0xAB),Ok(()));
+    /// assert!(register.try_set_bits(8..17,0).is_err());
+    /// assert_eq!(register.as_inner(),0xAB00);
+    /// ```
+    fn try_set_bits(&mut self, range: Range<usize>, value: u128) -> Result<(), FlagLsError> {
+        field_width(&range, self.len())?;
+        for (shift, idx) in range.enumerate() {
+            self.set(idx, (value >> shift) & 1 == 1);
+        }
+        Ok(())
+    }
+    /// Swaps the flags at two indices
+    /// # Panics
+    /// If either index is out of bounds
+    /// # Examples
+    /// ```
+    /// use packed_flags::B64;
+    /// use packed_flags::FlagLs;
+    ///
+    /// let mut flag_ls=B64::from_iter(vec![true,false,false]);
+    /// flag_ls.swap(0,2);
+    /// assert_eq!(flag_ls,B64::from_iter(vec![false,false,true]));
+    /// ```
+    fn swap(&mut self, first: usize, second: usize) {
+        let len = self.len();
+        assert!(first < len && second < len, "Cannot swap flags {first} and {second} of a list of length {len}");
+        let (l, r) = (self.get(first) == Some(true), self.get(second) == Some(true));
+        self.set(first, r);
+        self.set(second, l);
+    }
+    /// Swaps the flags at `first` and `second`, or returns an error if either is out of bounds
+    /// # Errors
+    /// Errors with the first index which is out of bounds, in which case the list is unchanged
+    /// # Examples
+    /// ```
+    /// use packed_flags::{B64, FlagLsError};
+    /// use packed_flags::FlagLs;
+    ///
+    /// let mut flag_ls=B64::from_iter(vec![true,false,false]);
+    /// assert_eq!(flag_ls.try_swap(0,2),Ok(()));
+    /// assert_eq!(flag_ls.try_swap(0,3),Err(FlagLsError::IndexOutOfBounds { idx: 3, len: 3 }));
+    /// assert_eq!(flag_ls,B64::from_iter(vec![false,false,true]));
+    /// ```
+    fn try_swap(&mut self, first: usize, second: usize) -> Result<(), FlagLsError> {
+        check_index(first, self.len())?;
+        check_index(second, self.len())?;
+        self.swap(first, second);
+        Ok(())
+    }
+    /// Reverses the order of the flags in place
+    /// # Examples
+    /// ```
+    /// use packed_flags::Blong;
+    /// use packed_flags::FlagLs;
+    ///
+    /// let mut flag_ls=Blong::from_iter((0..100).map(|idx| idx%3==0));
+    /// flag_ls.reverse();
+    /// assert_eq!(flag_ls,Blong::from_iter((0..100).map(|idx| (99-idx)%3==0)));
+    /// ```
+    fn reverse(&mut self) {
+        let len = self.len();
+        for idx in 0..len / 2 {
+            self.swap(idx, len - 1 - idx);
+        }
+    }
+    /// Keeps only the flags for which `f(index,flag)` returns true, in order, where index is the flag's position before any are removed
+    /// # Examples
+    /// ```
+    /// use packed_flags::B32;
+    /// use packed_flags::FlagLs;
+    ///
+    /// let mut flag_ls=B32::from_iter(vec![true,false,true,true,false]);
+    /// flag_ls.retain(|idx,flag| idx%2==0 || !flag);
+    /// assert_eq!(flag_ls,B32::from_iter(vec![true,false,true,false]));
+    /// ```
+    fn retain(&mut self, mut f: impl FnMut(usize, bool) -> bool) {
+        let mut kept = 0;
+        for idx in 0..self.len() {
+            let flag = self.get(idx) == Some(true);
+            if f(idx, flag) {
+                self.set(kept, flag);
+                kept += 1;
+            }
+        }
+        self.truncate(kept);
+    }
+    /// Moves every true flag to the front of the list and every false flag to the back, returning the number of true flags
+    /// # Examples
+    /// ```
+    /// use packed_flags::B32;
+    /// use packed_flags::FlagLs;
+    ///
+    /// let mut flag_ls=B32::from_iter(vec![false,true,false,true,true]);
+    /// assert_eq!(flag_ls.stable_partition(),3);
+    /// assert_eq!(flag_ls,B32::from_iter(vec![true,true,true,false,false]));
+    /// ```
+    fn stable_partition(&mut self) -> usize {
+        let count = self.and_count(self);
+        for idx in 0..self.len() {
+            self.set(idx, idx < count);
+        }
+        count
+    }
+    /// Collapses every run of equal flags into a single flag
+    /// # Examples
+    /// ```
+    /// use packed_flags::B32;
+    /// use packed_flags::FlagLs;
+    ///
+    /// let mut flag_ls=B32::from_iter(vec![true,true,false,false,false,true,false,false]);
+    /// flag_ls.dedup();
+    /// assert_eq!(flag_ls,B32::from_iter(vec![true,false,true,false]));
+    /// ```
+    fn dedup(&mut self) {
+        let mut last = None;
+        self.retain(|_, flag| last.replace(flag) != Some(flag));
+    }
+    /// Moves every flag from `other` onto the end of this list, leaving `other` empty
+    /// # Panics
+    /// Panics if the combined list would be longer than `MAX_LENGTH`
+    /// # Examples
+    /// ```
+    /// use packed_flags::B64;
+    /// use packed_flags::FlagLs;
+    ///
+    /// let mut flag_ls=B64::from_iter(vec![true,false]);
+    /// let mut other=B64::from_iter(vec![false,true]);
+    /// flag_ls.append(&mut other);
+    /// assert_eq!(flag_ls,B64::from_iter(vec![true,false,false,true]));
+    /// assert!(other.is_empty());
+    /// ```
+    fn append(&mut self, other: &mut Self) {
+        let start = self.len();
+        let new_len = start.checked_add(other.len()).filter(|len| *len <= Self::MAX_LENGTH);
+        assert!(new_len.is_some(), "Cannot append {} flags to a list of length {start}, the maximum length is {}", other.len(), Self::MAX_LENGTH);
+        self.set_len(start + other.len());
+        copy_flags(other, 0, self, start, other.len());
+        other.clear();
+    }
+    /// Attempts to move every flag from `other` onto the end of this list, leaving `other` empty
+    /// # Errors
+    /// Errors if the combined list would be longer than `MAX_LENGTH`, in which case neither list is changed
+    /// # Examples
+    /// ```
+    /// use packed_flags::{B32, FlagLsError};
+    /// use packed_flags::FlagLs;
+    ///
+    /// let mut flag_ls=B32::all_true(20);
+    /// let mut other=B32::all_false(20);
+    /// assert_eq!(flag_ls.try_append(&mut other),Err(FlagLsError::MaximumLengthExceeded { mx_len: 32, attempt_len: 40 }));
+    /// other.truncate(12);
+    /// assert_eq!(flag_ls.try_append(&mut other),Ok(()));
+    /// assert_eq!(flag_ls.len(),32);
+    /// ```
+    fn try_append(&mut self, other: &mut Self) -> Result<(), FlagLsError> {
+        check_len(Self::MAX_LENGTH, self.len().saturating_add(other.len()))?;
+        self.append(other);
+        Ok(())
+    }
+    /// Splits the list in two at `at`, keeping the flags before `at` and returning the rest
+    /// # Panics
+    /// Panics if `at>self.len()`
+    /// # Examples
+    /// ```
+    /// use packed_flags::Blong;
+    /// use packed_flags::FlagLs;
+    ///
+    /// let mut flag_ls=Blong::from_iter((0..100).map(|idx| idx%3==0));
+    /// let tail=flag_ls.split_off(70);
+    /// assert_eq!(flag_ls,Blong::from_iter((0..70).map(|idx| idx%3==0)));
+    /// assert_eq!(tail,Blong::from_iter((70..100).map(|idx| idx%3==0)));
+    /// ```
+    #[must_use = "use `truncate` to throw away the flags after `at`"]
+    fn split_off(&mut self, at: usize) -> Self {
+        let len = self.len();
+        assert!(at <= len, "Cannot split a list of length {len} at {at}");
+        let mut out = Self::default();
+        out.set_len(len - at);
+        copy_flags(self, at, &mut out, 0, len - at);
+        self.truncate(at);
+        out
+    }
+    /// Attempts to split the list in two at `at`, keeping the flags before `at` and returning the rest
+    /// # Errors
+    /// Errors if `at>self.len()`, in which case the list is unchanged
+    /// # Examples
+    /// ```
+    /// use packed_flags::{B32, FlagLsError};
+    /// use packed_flags::FlagLs;
+    ///
+    /// let mut flag_ls=B32::from_iter(vec![true,false,true]);
+    /// assert_eq!(flag_ls.try_split_off(4),Err(FlagLsError::IndexOutOfBounds { idx: 4, len: 3 }));
+    /// assert_eq!(flag_ls.try_split_off(1),Ok(B32::from_iter(vec![false,true])));
+    /// assert_eq!(flag_ls,B32::from_iter(vec![true]));
+    /// ```
+    fn try_split_off(&mut self, at: usize) -> Result<Self, FlagLsError> {
+        if at > self.len() {
+            return Err(FlagLsError::IndexOutOfBounds { idx: at, len: self.len() });
+        }
+        Ok(self.split_off(at))
+    }
+    /// Splits the list in two at `at`, returning the flags before and from `at`
+    /// # Panics
+    /// Panics if `at>self.len()`
+    /// # Examples
+    /// ```
+    /// use packed_flags::B32;
+    /// use packed_flags::FlagLs;
+    ///
+    /// let (head,tail)=B32::from_iter(vec![true,false,true]).split_at(1);
+    /// assert_eq!(head,B32::from_iter(vec![true]));
+    /// assert_eq!(tail,B32::from_iter(vec![false,true]));
+    /// ```
+    #[must_use]
+    fn split_at(mut self, at: usize) -> (Self, Self) {
+        let tail = self.split_off(at);
+        (self, tail)
+    }
+    /// Removes the flags in `range`, returning them as a new list
+    /// # Panics
+    /// Panics if the range starts after it ends or ends after the end of the list
+    /// # Examples
+    /// ```
+    /// use packed_flags::B64;
+    /// use packed_flags::FlagLs;
+    ///
+    /// let mut flag_ls=B64::from_iter(vec![true,false,false,true,true]);
+    /// assert_eq!(flag_ls.drain(1..3),B64::from_iter(vec![false,false]));
+    /// assert_eq!(flag_ls,B64::from_iter(vec![true,true,true]));
+    /// ```
+    #[must_use = "the removed flags are returned as a new list"]
+    fn drain(&mut self, range: Range<usize>) -> Self {
+        assert!(range.start <= range.end && range.end <= self.len(), "Cannot drain {range:?} from a list of length {}", self.len());
+        let mut tail = self.split_off(range.end);
+        let out = self.split_off(range.start);
+        self.append(&mut tail);
+        out
+    }
+    /// Attempts to remove the flags in `range`, returning them as a new list
+    /// # Errors
+    /// Errors with [`FlagLsError::InvalidRange`] if the range starts after it ends, or [`FlagLsError::IndexOutOfBounds`] if it ends after the end of the list.
+    /// The list is unchanged on error
+    /// # Examples
+    /// ```
+    /// use packed_flags::{B64, FlagLsError};
+    /// use packed_flags::FlagLs;
+    ///
+    /// let mut flag_ls=B64::from_iter(vec![true,false,false,true,true]);
+    /// assert_eq!(flag_ls.try_drain(3..6),Err(FlagLsError::IndexOutOfBounds { idx: 5, len: 5 }));
+    /// assert_eq!(flag_ls.try_drain(3..1),Err(FlagLsError::InvalidRange { start: 3, end: 1 }));
+    /// assert_eq!(flag_ls.try_drain(1..3),Ok(B64::from_iter(vec![false,false])));
+    /// assert_eq!(flag_ls,B64::from_iter(vec![true,true,true]));
+    /// ```
+    fn try_drain(&mut self, range: Range<usize>) -> Result<Self, FlagLsError> {
+        check_range(&range, self.len())?;
+        Ok(self.drain(range))
+    }
+    /// Replaces the flags in `range` with the flags from `replace_with`, returning the flags that were removed
+    /// # Panics
+    /// Panics if the range starts after it ends or ends after the end of the list, or if the new list would be longer than `MAX_LENGTH`
+    /// # Examples
+    /// ```
+    /// use packed_flags::B64;
+    /// use packed_flags::FlagLs;
+    ///
+    /// let mut flag_ls=B64::from_iter(vec![true,false,false,true]);
+    /// let removed=flag_ls.splice(1..3,vec![true,true,true]);
+    /// assert_eq!(removed,B64::from_iter(vec![false,false]));
+    /// assert_eq!(flag_ls,B64::from_iter(vec![true,true,true,true,true]));
+    /// ```
+    #[must_use = "the removed flags are returned as a new list"]
+    fn splice(&mut self, range: Range<usize>, replace_with: impl IntoIterator<Item = bool>) -> Self {
+        assert!(range.start <= range.end && range.end <= self.len(), "Cannot splice {range:?} in a list of length {}", self.len());
+        let mut tail = self.split_off(range.end);
+        let out = self.split_off(range.start);
+        for flag in replace_with {
+            self.push(flag);
+        }
+        self.append(&mut tail);
+        out
+    }
+    /// Attempts to replace the flags in `range` with the flags from `replace_with`, returning the flags that were removed
+    /// # Errors
+    /// Errors if the range starts after it ends or ends after the end of the list, or the new list would be longer than `MAX_LENGTH`, in which case the list is unchanged.
+    /// Like [`splice`][FlagLs::splice], `replace_with` is read to the end, so the error's `attempt_len` is the length the list would have had
+    /// # Examples
+    /// ```
+    /// use packed_flags::{B32, FlagLsError};
+    /// use packed_flags::FlagLs;
+    ///
+    /// let mut flag_ls=B32::all_true(30);
+    /// assert_eq!(flag_ls.try_splice(0..1,vec![false;4]),Err(FlagLsError::MaximumLengthExceeded { mx_len: 32, attempt_len: 33 }));
+    /// assert_eq!(flag_ls.try_splice(0..1,vec![false;10]),Err(FlagLsError::MaximumLengthExceeded { mx_len: 32, attempt_len: 39 }));
+    /// assert_eq!(flag_ls.try_splice(0..2,vec![false;4]),Ok(B32::all_true(2)));
+    /// assert_eq!(flag_ls.get_bits(0..5),0b10000);
+    /// ```
+    fn try_splice(&mut self, range: Range<usize>, replace_with: impl IntoIterator<Item = bool>) -> Result<Self, FlagLsError> {
+        check_range(&range, self.len())?;
+        // the replacement is collected first so the list is untouched if it is too long
+        let kept = self.len() - range.len();
+        let mut replace_with = replace_with.into_iter();
+        let mut new = Self::default();
+        for flag in replace_with.by_ref().take(Self::MAX_LENGTH - kept) {
+            new.try_push(flag)?;
+        }
+        let extra = replace_with.count();
+        if extra > 0 {
+            return Err(FlagLsError::MaximumLengthExceeded { mx_len: Self::MAX_LENGTH, attempt_len: (kept + new.len()).saturating_add(extra) });
+        }
+        let mut tail = self.split_off(range.end);
+        let out = self.split_off(range.start);
+        self.append(&mut new);
+        self.append(&mut tail);
+        Ok(out)
+    }
+    /// Counts the positions where both lists have a true flag
+    ///
+    /// Lists of different lengths are compared as if the shorter one were padded with false
+    /// # Examples
+    /// ```
+    /// use packed_flags::B64;
+    /// use packed_flags::FlagLs;
+    ///
+    /// let a=B64::from_iter(vec![true,true,false,true]);
+    /// let b=B64::from_iter(vec![true,false,true]);
+    /// assert_eq!(a.and_count(&b),1);
+    /// ```
+    fn and_count(&self, other: &Self) -> usize {
+        (0..self.len().min(other.len())).filter(|idx| self.get(*idx) == Some(true) && other.get(*idx) == Some(true)).count()
+    }
+    /// Counts the positions where either list has a true flag
+    ///
+    /// Lists of different lengths are compared as if the shorter one were padded with false
+    /// # Examples
+    /// ```
+    /// use packed_flags::B64;
+    /// use packed_flags::FlagLs;
+    ///
+    /// let a=B64::from_iter(vec![true,true,false,true]);
+    /// let b=B64::from_iter(vec![true,false,true]);
+    /// assert_eq!(a.or_count(&b),4);
+    /// ```
+    fn or_count(&self, other: &Self) -> usize {
+        (0..self.len().max(other.len())).filter(|idx| self.get(*idx) == Some(true) || other.get(*idx) == Some(true)).count()
+    }
+    /// Counts the positions where exactly one of the lists has a true flag
+    ///
+    /// Lists of different lengths are compared as if the shorter one were padded with false
+    /// # Examples
+    /// ```
+    /// use packed_flags::B64;
+    /// use packed_flags::FlagLs;
+    ///
+    /// let a=B64::from_iter(vec![true,true,false,true]);
+    /// let b=B64::from_iter(vec![true,false,true]);
+    /// assert_eq!(a.xor_count(&b),3);
+    /// ```
+    fn xor_count(&self, other: &Self) -> usize {
+        (0..self.len().max(other.len())).filter(|idx| (self.get(*idx) == Some(true)) != (other.get(*idx) == Some(true))).count()
+    }
+    /// The number of positions where the lists differ, the same as [`xor_count`][FlagLs::xor_count]
+    /// # Examples
+    /// ```
+    /// use packed_flags::Blong;
+    /// use packed_flags::FlagLs;
+    ///
+    /// let a=Blong::all_true(100);
+    /// let b=Blong::all_true(90);
+    /// assert_eq!(a.hamming_distance(&b),10);
+    /// ```
+    fn hamming_distance(&self, other: &Self) -> usize {
+        self.xor_count(other)
+    }
+    /// The Jaccard similarity of the sets of true flags, the size of their intersection over the size of their union
+    ///
+    /// Two lists with no true flags are considered identical, with a similarity of 1
+    /// # Examples
+    /// ```
+    /// use packed_flags::B64;
+    /// use packed_flags::FlagLs;
+    ///
+    /// let a=B64::from_iter(vec![true,true,false,true]);
+    /// let b=B64::from_iter(vec![true,false,true]);
+    /// assert!((a.jaccard(&b)-0.25).abs()<1e-9);
+    /// ```
+    #[allow(clippy::cast_precision_loss)]
+    fn jaccard(&self, other: &Self) -> f64 {
+        let union = self.or_count(other);
+        if union == 0 {
+            1.0
+        } else {
+            self.and_count(other) as f64 / union as f64
+        }
+    }
+    /// The Sørensen–Dice similarity of the sets of true flags, twice the size of their intersection over the sum of their sizes
+    ///
+    /// Two lists with no true flags are considered identical, with a similarity of 1
+    /// # Examples
+    /// ```
+    /// use packed_flags::B64;
+    /// use packed_flags::FlagLs;
+    ///
+    /// let a=B64::from_iter(vec![true,true,false,true]);
+    /// let b=B64::from_iter(vec![true,false,true]);
+    /// assert!((a.dice(&b)-0.4).abs()<1e-9);
+    /// ```
+    #[allow(clippy::cast_precision_loss)]
+    fn dice(&self, other: &Self) -> f64 {
+        let total = self.and_count(self) + other.and_count(other);
+        if total == 0 {
+            1.0
+        } else {
+            2.0 * self.and_count(other) as f64 / total as f64
+        }
+    }
+    /// The cosine similarity of the lists, treating each as a vector of 0s and 1s
+    ///
+    /// Two lists with no true flags are considered identical, with a similarity of 1, while a list with no true flags has a similarity of 0 to any other list
+    /// # Examples
+    /// ```
+    /// use packed_flags::B64;
+    /// use packed_flags::FlagLs;
+    ///
+    /// let a=B64::from_iter(vec![true,true,false,true]);
+    /// let b=B64::from_iter(vec![true,false,false,false]);
+    /// assert!((a.cosine(&b)-1.0/3.0_f64.sqrt()).abs()<1e-9);
+    /// ```
+    #[allow(clippy::cast_precision_loss)]
+    fn cosine(&self, other: &Self) -> f64 {
+        let (l_count, r_count) = (self.and_count(self), other.and_count(other));
+        if l_count == 0 && r_count == 0 {
+            1.0
+        } else if l_count == 0 || r_count == 0 {
+            0.0
+        } else {
+            self.and_count(other) as f64 / (l_count as f64 * r_count as f64).sqrt()
+        }
+    }
     /// get an iterator over all flags in the list
     /// # Examples
     /// ```
@@ -265,7 +909,7 @@ pub trait FlagLs: Sized+Default
     /// assert_eq!(itr.next(),Some(true));
     /// assert_eq!(itr.next(),None);
     /// ```
-    fn iter(&self) -> flag_iter::Iter<Self>;
+    fn iter(&self) -> flag_iter::Iter<'_, Self>;
     /// build a compact list of flags from an iterator-like of flags, consuming it
     /// 
     /// If you are trying to convert an `InitialFlagLs` to a `DesiredFlagLs`, and `DesiredFlagLs` implements `From<InitialFlagLs>` or `TryFrom<InitialFlagLs>` then the assosiated conversion method is preferable to `DesiredFlags::from_iter(thing.iter())`
@@ -312,6 +956,37 @@ pub trait FlagLs: Sized+Default
         }
         Ok(out)
     }
+    /// Convert to any other kind of list of flags, copying up to 128 flags at a time
+    ///
+    /// This works between any two kinds of list, so is useful in generic code, but where `From` or `TryFrom` is implemented it is at least as fast
+    /// # Errors
+    /// Errors when the list is longer than `T::MAX_LENGTH`
+    /// # Examples
+    /// ```
+    /// # use std::error::Error;
+    /// #
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// use packed_flags::{Blong, B32, B64};
+    /// use packed_flags::FlagLs;
+    ///
+    /// let flag_ls=Blong::from_iter(vec![true,false,true]);
+    /// let small: B32=flag_ls.convert()?;
+    /// assert_eq!(small,B32::from_iter(vec![true,false,true]));
+    /// assert_eq!(small.convert::<B64>()?,B64::from(small));
+    /// assert!(Blong::all_true(33).convert::<B32>().is_err());
+    /// # Ok(())
+    /// # }
+    /// ```
+    fn convert<T: FlagLs>(&self) -> Result<T, FlagLsError> {
+        let len = self.len();
+        if len > T::MAX_LENGTH {
+            return Err(FlagLsError::MaximumLengthExceeded { mx_len: T::MAX_LENGTH, attempt_len: len });
+        }
+        let mut out = T::default();
+        out.set_len(len);
+        copy_flags(self, 0, &mut out, 0, len);
+        Ok(out)
+    }
     /// build a list of flags of the specified length which is all true
     /// # Panics
     /// Panics when `len>MAX_LENGTH`
@@ -333,6 +1008,21 @@ pub trait FlagLs: Sized+Default
         }
         out
     }
+    /// Attempt to build a list of flags of the specified length which is all true
+    /// # Errors
+    /// Errors when `len>MAX_LENGTH`
+    /// # Examples
+    /// ```
+    /// use packed_flags::{B64, FlagLsError};
+    /// use packed_flags::FlagLs;
+    ///
+    /// assert_eq!(B64::try_all_true(10),Ok(B64::all_true(10)));
+    /// assert_eq!(B64::try_all_true(65),Err(FlagLsError::MaximumLengthExceeded { mx_len: 64, attempt_len: 65 }));
+    /// ```
+    fn try_all_true(len: usize) -> Result<Self, FlagLsError> {
+        check_len(Self::MAX_LENGTH, len)?;
+        Ok(Self::all_true(len))
+    }
     /// build a list of flags which is all false
     /// # Panics
     /// Panics when `len>MAX_LENGTH`
@@ -354,6 +1044,219 @@ pub trait FlagLs: Sized+Default
         }
         out
     }
+    /// Attempt to build a list of flags of the specified length which is all false
+    /// # Errors
+    /// Errors when `len>MAX_LENGTH`
+    /// # Examples
+    /// ```
+    /// use packed_flags::{B64, FlagLsError};
+    /// use packed_flags::FlagLs;
+    ///
+    /// assert_eq!(B64::try_all_false(10),Ok(B64::all_false(10)));
+    /// assert_eq!(B64::try_all_false(65),Err(FlagLsError::MaximumLengthExceeded { mx_len: 64, attempt_len: 65 }));
+    /// ```
+    fn try_all_false(len: usize) -> Result<Self, FlagLsError> {
+        check_len(Self::MAX_LENGTH, len)?;
+        Ok(Self::all_false(len))
+    }
+    /// Build a list of `len` random flags, where each flag is true with probability `p`
+    ///
+    /// `rng` can be any source of uniformly random words, such as [`SplitMix64`][crate::SplitMix64]
+    /// # Panics
+    /// Panics when `len>MAX_LENGTH` or `p` is not between 0 and 1
+    /// # Examples
+    /// ```
+    /// use packed_flags::{Blong, SplitMix64};
+    /// use packed_flags::FlagLs;
+    ///
+    /// let mut rng=SplitMix64::new(7);
+    /// let flag_ls=Blong::random(1000,0.1,|| rng.next_u64());
+    /// let count=flag_ls.iter().filter(|flag| *flag).count();
+    /// assert!(count>50 && count<150);
+    /// assert_eq!(Blong::random(100,1.0,|| rng.next_u64()),Blong::all_true(100));
+    /// ```
+    #[must_use]
+    fn random(len: usize, p: f64, mut rng: impl FnMut() -> u64) -> Self {
+        assert!((0.0..=1.0).contains(&p), "Probability must be between 0 and 1, got {p}");
+        Self::from_iter((0..len).map(|_| random::unit(&mut rng) < p))
+    }
+    /// Build a list of `len` flags with exactly `k` true flags, with every choice of `k` flags equally likely
+    /// # Panics
+    /// Panics when `len>MAX_LENGTH` or `k>len`
+    /// # Examples
+    /// ```
+    /// use packed_flags::{B128, SplitMix64};
+    /// use packed_flags::FlagLs;
+    ///
+    /// let mut rng=SplitMix64::new(7);
+    /// let flag_ls=B128::choose(100,10,|| rng.next_u64());
+    /// assert_eq!(flag_ls.len(),100);
+    /// assert_eq!(flag_ls.iter().filter(|flag| *flag).count(),10);
+    /// ```
+    #[must_use]
+    fn choose(len: usize, k: usize, mut rng: impl FnMut() -> u64) -> Self {
+        assert!(k <= len, "Cannot choose {k} flags from {len}");
+        let mut out = Self::all_false(len);
+        // Floyd's algorithm, which picks each flag at most once without any extra storage
+        for idx in len - k..len {
+            let pick = random::below(&mut rng, idx + 1);
+            if out.get(pick) == Some(true) {
+                out.set(idx, true);
+            } else {
+                out.set(pick, true);
+            }
+        }
+        out
+    }
+    /// Returns the index of a uniformly random true flag, or none if there are no true flags
+    /// # Examples
+    /// ```
+    /// use packed_flags::{B32, SplitMix64};
+    /// use packed_flags::FlagLs;
+    ///
+    /// let mut rng=SplitMix64::new(7);
+    /// let flag_ls=B32::from_iter(vec![false,true,false,true]);
+    /// let idx=flag_ls.sample(|| rng.next_u64());
+    /// assert!(idx==Some(1) || idx==Some(3));
+    /// assert_eq!(B32::all_false(10).sample(|| rng.next_u64()),None);
+    /// ```
+    fn sample(&self, mut rng: impl FnMut() -> u64) -> Option<usize> {
+        let count = self.and_count(self);
+        if count == 0 {
+            return None;
+        }
+        let nth = random::below(&mut rng, count);
+        self.iter().enumerate().filter(|(_, flag)| *flag).nth(nth).map(|(idx, _)| idx)
+    }
+    /// Randomly reorders the flags in place, with every order equally likely
+    /// # Examples
+    /// ```
+    /// use packed_flags::{B64, SplitMix64};
+    /// use packed_flags::FlagLs;
+    ///
+    /// let mut rng=SplitMix64::new(7);
+    /// let mut flag_ls=B64::from_iter(vec![true,true,true,false,false,false]);
+    /// flag_ls.shuffle(|| rng.next_u64());
+    /// assert_eq!(flag_ls.len(),6);
+    /// assert_eq!(flag_ls.iter().filter(|flag| *flag).count(),3);
+    /// ```
+    fn shuffle(&mut self, mut rng: impl FnMut() -> u64) {
+        for idx in (1..self.len()).rev() {
+            let pick = random::below(&mut rng, idx + 1);
+            let (l, r) = (self.get(idx) == Some(true), self.get(pick) == Some(true));
+            if l != r {
+                self.set(idx, r);
+                self.set(pick, l);
+            }
+        }
+    }
+    /// Writes the flags as a self describing bitmap file, see [`BitmapFile`][crate::BitmapFile] for reading it back without copying
+    ///
+    /// The file has a header with the format version and number of flags, then the flags either one bit each or as run lengths,
+    /// whichever is smaller, then a CRC32 checksum of everything before it
+    /// # Errors
+    /// Errors if the writer fails
+    /// # Examples
+    /// ```
+    /// # use std::error::Error;
+    /// #
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// use packed_flags::{Blong, B128};
+    /// use packed_flags::FlagLs;
+    ///
+    /// let flags=B128::from_iter(vec![true,false,true]);
+    /// let mut file=Vec::new();
+    /// flags.write_to(&mut file)?;
+    /// assert_eq!(B128::read_from(file.as_slice())?,flags);
+    /// assert_eq!(Blong::read_from(file.as_slice())?,Blong::from(flags));
+    /// assert!(B128::read_from(&file[..file.len()-1]).is_err());
+    /// # Ok(())
+    /// # }
+    /// ```
+    fn write_to(&self, writer: impl Write) -> Result<(), FormatError> {
+        bitmap_file::write(self, writer)
+    }
+    /// Reads flags from a bitmap file written by [`write_to`][FlagLs::write_to]
+    /// # Errors
+    /// Errors if the reader fails, the file is truncated or corrupt, or it holds more flags than `MAX_LENGTH`
+    /// # Examples
+    /// See [`write_to`][FlagLs::write_to]
+    fn read_from(reader: impl Read) -> Result<Self, FormatError> {
+        bitmap_file::read(reader)
+    }
+    /// Formats the indices of the true flags in the Linux "cpulist" syntax used by `/sys` and `taskset -c`, such as `0-3,8,10-15`
+    ///
+    /// Runs of true flags are written as inclusive ranges, and a list with no true flags is written as an empty string
+    /// # Examples
+    /// ```
+    /// # use std::error::Error;
+    /// #
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// use packed_flags::{Blong, B32, CpuTextError};
+    /// use packed_flags::FlagLs;
+    ///
+    /// let online=B32::from_cpulist("0-3,8,10-15\n")?;
+    /// assert_eq!(online.len(),16);
+    /// assert_eq!(online.to_cpulist(),"0-3,8,10-15");
+    /// assert_eq!(Blong::from_cpulist("0-9:2/5")?.to_cpulist(),"0-1,5-6");
+    /// assert_eq!(B32::from_cpulist("0-3,8-"),Err(CpuTextError::MissingNumber{pos:6}));
+    /// assert_eq!(B32::from_cpulist("0-3,12-8"),Err(CpuTextError::ReversedRange{pos:4,start:12,end:8}));
+    /// # Ok(())
+    /// # }
+    /// ```
+    fn to_cpulist(&self) -> String {
+        cpu_text::format_cpulist(self)
+    }
+    /// Parses a list of flags from the Linux "cpulist" syntax, a comma separated list of indices and inclusive ranges such as `0-3,8,10-15`
+    ///
+    /// Ranges may have a stride, as in the kernel's `0-15:2/4`, which sets the first 2 flags of every 4 in `0-15`.
+    /// Surrounding whitespace, such as the newline at the end of a `/sys` file, is ignored.
+    /// The list is just long enough to hold the last true flag, so formatting it with [`to_cpulist`][FlagLs::to_cpulist] gives back the same true flags
+    /// # Errors
+    /// Errors with the byte offset of the bad token if the text is not a valid cpulist, or a listed flag does not fit in `MAX_LENGTH`
+    /// # Examples
+    /// See [`to_cpulist`][FlagLs::to_cpulist]
+    fn from_cpulist(text: &str) -> Result<Self, CpuTextError> {
+        cpu_text::parse_cpulist(text)
+    }
+    /// Formats the flags in the Linux "cpumask" syntax used by `/sys` and `taskset`, such as `ff,0000000f`
+    ///
+    /// The flags are written as hex digits with flag 0 as the lowest bit of the last digit, in comma separated groups of 8 digits.
+    /// The first group only has as many digits as are needed to hold every flag, so a list of length 0 is written as an empty string
+    /// # Examples
+    /// ```
+    /// # use std::error::Error;
+    /// #
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// use packed_flags::{B32, B64, CpuTextError};
+    /// use packed_flags::FlagLs;
+    ///
+    /// let mut mask=B64::all_false(40);
+    /// mask.set(0,true);
+    /// mask.set(35,true);
+    /// assert_eq!(mask.to_cpumask(),"08,00000001");
+    /// assert_eq!(B64::from_cpumask("08,00000001")?,mask);
+    /// assert_eq!(B32::from_cpumask("00000000,0000000f\n")?,B32::from_iter((0..32).map(|idx| idx<4)));
+    /// assert_eq!(B32::from_cpumask("0x1,00000000"),Err(CpuTextError::IndexTooLarge{pos:2,idx:32,mx_len:32}));
+    /// assert_eq!(B32::from_cpumask("ff,0g"),Err(CpuTextError::UnexpectedChar{pos:4,found:'g'}));
+    /// # Ok(())
+    /// # }
+    /// ```
+    fn to_cpumask(&self) -> String {
+        cpu_text::format_cpumask(self)
+    }
+    /// Parses a list of flags from the Linux "cpumask" syntax, comma separated groups of up to 8 hex digits such as `ff,0000000f`
+    ///
+    /// Each group holds 32 flags, with flag 0 as the lowest bit of the last group. A leading `0x` and surrounding whitespace are ignored.
+    /// The list has 4 flags for each digit of the first group and 32 for each later group, which is exactly the length written by [`to_cpumask`][FlagLs::to_cpumask]
+    /// for lists whose length is a multiple of 4. When that is more than `MAX_LENGTH` the list is cut to `MAX_LENGTH`, as long as only false flags are lost
+    /// # Errors
+    /// Errors with the byte offset of the bad token if the text is not a valid cpumask, or a true flag does not fit in `MAX_LENGTH`
+    /// # Examples
+    /// See [`to_cpumask`][FlagLs::to_cpumask]
+    fn from_cpumask(text: &str) -> Result<Self, CpuTextError> {
+        cpu_text::parse_cpumask(text)
+    }
     /// Returns true when there are no flags in the list
     /// # Examples
     /// ```
@@ -0,0 +1,132 @@
+use crate::{FlagLs, FlagLsError};
+/// Hands out small integer ids, always picking the lowest free one
+///
+/// Occupancy is tracked with one flag per id in a `T`. The list of flags grows as more ids are needed,
+/// so an allocator backed by a [`Blong`][crate::Blong] never runs out, while fixed width backends are limited to their `MAX_LENGTH`
+/// # Examples
+/// ```
+/// # use std::error::Error;
+/// #
+/// # fn main() -> Result<(), Box<dyn Error>> {
+/// use packed_flags::{IdAllocator, B32};
+///
+/// let mut ids: IdAllocator<B32>=IdAllocator::new();
+/// assert_eq!(ids.allocate()?,0);
+/// assert_eq!(ids.allocate()?,1);
+/// assert_eq!(ids.allocate()?,2);
+/// assert!(ids.free(1));
+/// assert_eq!(ids.allocate()?,1);
+/// assert_eq!(ids.iter().collect::<Vec<_>>(),vec![0,1,2]);
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct IdAllocator<T: FlagLs> {
+    flags: T,
+}
+impl<T: FlagLs> IdAllocator<T> {
+    /// Create an allocator with no ids in use
+    #[must_use]
+    pub fn new() -> Self {
+        Self { flags: T::default() }
+    }
+    /// Allocates the lowest free id
+    /// # Errors
+    /// Errors with [`FlagLsError::MaximumLengthExceeded`] when every id the backend can hold is in use
+    pub fn allocate(&mut self) -> Result<usize, FlagLsError> {
+        if let Some(id) = self.first_free() {
+            self.flags.set(id, true);
+            Ok(id)
+        } else {
+            self.flags.try_push(true)?;
+            Ok(self.flags.len() - 1)
+        }
+    }
+    /// The lowest id not in use within the list of flags, found 128 flags at a time
+    fn first_free(&self) -> Option<usize> {
+        let len = self.flags.len();
+        (0..len).step_by(128).find_map(|start| {
+            let end = len.min(start + 128);
+            let id = start + usize::try_from(self.flags.get_bits(start..end).trailing_ones()).expect("Infalible");
+            (id < end).then_some(id)
+        })
+    }
+    /// Allocates `count` consecutive ids, returning the first one
+    ///
+    /// The run used is the lowest one that fits. Allocating a run of 0 ids always succeeds and allocates nothing
+    /// # Errors
+    /// Errors with [`FlagLsError::MaximumLengthExceeded`] when there is no run of `count` free ids the backend can hold.
+    /// Nothing is allocated on error
+    /// # Examples
+    /// ```
+    /// # use std::error::Error;
+    /// #
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// use packed_flags::{IdAllocator, B32};
+    ///
+    /// let mut ids: IdAllocator<B32>=IdAllocator::new();
+    /// ids.allocate_run(4)?;
+    /// ids.free(1);
+    /// ids.free(2);
+    /// assert_eq!(ids.allocate_run(3)?,4);
+    /// assert_eq!(ids.allocate_run(2)?,1);
+    /// assert!(ids.allocate_run(30).is_err());
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn allocate_run(&mut self, count: usize) -> Result<usize, FlagLsError> {
+        let mut start = 0;
+        let mut run = 0;
+        for (idx, flag) in self.flags.iter().enumerate() {
+            if run == count {
+                break;
+            }
+            if flag {
+                start = idx + 1;
+                run = 0;
+            } else {
+                run += 1;
+            }
+        }
+        let end = start.checked_add(count).ok_or(FlagLsError::MaximumLengthExceeded { mx_len: T::MAX_LENGTH, attempt_len: usize::MAX })?;
+        if end > T::MAX_LENGTH {
+            return Err(FlagLsError::MaximumLengthExceeded { mx_len: T::MAX_LENGTH, attempt_len: end });
+        }
+        while self.flags.len() < end {
+            self.flags.push(false);
+        }
+        for idx in start..end {
+            self.flags.set(idx, true);
+        }
+        Ok(start)
+    }
+    /// Frees an id so it can be handed out again, returning false if it was not in use
+    pub fn free(&mut self, id: usize) -> bool {
+        self.flags.try_set(id, false) == Some(true)
+    }
+    /// Returns true if the id is currently in use
+    pub fn is_allocated(&self, id: usize) -> bool {
+        self.flags.get(id) == Some(true)
+    }
+    /// The number of ids in use
+    pub fn len(&self) -> usize {
+        self.flags.and_count(&self.flags)
+    }
+    /// Returns true when no ids are in use
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+    /// Frees every id
+    pub fn clear(&mut self) {
+        self.flags.clear();
+    }
+    /// Get an iterator over the ids in use, in increasing order
+    pub fn iter(&self) -> impl Iterator<Item = usize> + '_ {
+        self.flags.iter().enumerate().filter_map(|(idx, flag)| flag.then_some(idx))
+    }
+    /// Get a reference to the underlying flags, where flag `i` is true if id `i` is in use
+    pub const fn as_flags(&self) -> &T {
+        &self.flags
+    }
+}
@@ -2,40 +2,104 @@
 //!Provides various packed lists of flags(ie equivalent to `Vec<bool>`).
 //!Useful anywhere you are tempted to use `Vec<bool>` or `[bool]`, but want some amount of memory efficiency
 mod bit128;
+mod bitauto;
 mod bit32;
 mod bit64;
 mod bitlong;
+mod bitmap_file;
+mod bitmatrix;
 mod bitsize;
+mod bitstream;
+mod bloom;
+mod concat;
+mod cpu_text;
+mod flag_debug;
+mod flag_schema;
+mod flag_view;
 mod flagls;
+mod id_alloc;
+mod morton;
+mod random;
 pub mod flag_iter;
+pub mod flag_set;
+pub mod packed_ints;
 use std::error::Error;
 use std::fmt::{Display};
 
 pub use crate::bit128::B128;
+pub use crate::bitauto::BAuto;
 pub use crate::bit32::B32;
 pub use crate::bit64::B64;
 pub use crate::bitlong::Blong;
+pub use crate::bitmap_file::{encode_bitmap, BitmapFile, Encoding, FormatError};
+pub use crate::bitmatrix::BMatrix;
 pub use crate::bitsize::Bsize;
+pub use crate::bitstream::{BitReader, BitWriter};
+pub use crate::bloom::{BloomFilter, CountingBloomFilter, DefaultBuildHasher, FnvHasher};
+pub use crate::concat::Concat;
+pub use crate::cpu_text::CpuTextError;
 pub use crate::flagls::FlagLs;
+pub use crate::flag_schema::{FlagSchema, SchemaError};
+pub use crate::flag_set::{FlagIndex, FlagSet};
+pub use crate::flag_view::{BitOrder, FlagView, FlagViewMut};
+pub use crate::id_alloc::IdAllocator;
+pub use crate::morton::{morton_decode_2d, morton_decode_3d, morton_encode_2d, morton_encode_3d};
+pub use crate::packed_ints::PackedInts;
+pub use crate::random::SplitMix64;
+#[cfg(feature = "derive")]
+pub use packed_flags_derive::Flag;
 #[derive(Clone,Copy,PartialEq, Eq,Hash,Debug)]
 ///Represents errors that can occur for a [`FlagLs`]
 pub enum FlagLsError{
     IndexOutOfBounds{idx:usize,len:usize},
-    MaximumLengthExceeded{mx_len:usize,attempt_len:usize}
+    MaximumLengthExceeded{mx_len:usize,attempt_len:usize},
+    AllocationFailed{attempt_len:usize},
+    InvalidRange{start:usize,end:usize}
 }
 impl Error for FlagLsError{}
 impl Display for FlagLsError{
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match &self{
             Self::IndexOutOfBounds { idx, len }=>write!(f, "attempted to access out of bounds index {idx} of flag list of length {len}"),
-            Self::MaximumLengthExceeded { mx_len, attempt_len }=>write!(f, "flag list has maximum length {mx_len}, attempted to increase this to {attempt_len}")
+            Self::MaximumLengthExceeded { mx_len, attempt_len }=>write!(f, "flag list has maximum length {mx_len}, attempted to increase this to {attempt_len}"),
+            Self::AllocationFailed { attempt_len }=>write!(f, "failed to allocate space for a flag list of length {attempt_len}"),
+            Self::InvalidRange { start, end }=>write!(f, "range {start}..{end} starts after it ends")
         }
     }
 }
 #[cfg(test)]
+#[allow(clippy::bool_assert_comparison)]
 // runs all the doc test on the other ones
 mod tests {
     use super::*;
+    /// The lengths every backend is checked at, the edges of the fixed width lists and word boundaries of the growable ones
+    fn lengths<T: FlagLs>() -> Vec<usize> {
+        if T::MAX_LENGTH <= 128 {
+            vec![0, 1, T::MAX_LENGTH / 2, T::MAX_LENGTH - 1, T::MAX_LENGTH]
+        } else {
+            vec![0, 1, 63, 64, 65, 127, 128, 129, 200, 511]
+        }
+    }
+    /// Runs `check::<T>(len)` for every backend at each of its [`lengths`], or `check::<T>(len, rng)` to also pass a generator seeded from the length
+    macro_rules! each_backend {
+        ($check:ident($len:ident)) => {
+            each_backend!(@each $check($len): B32, B64, B128, Bsize, Blong, BAuto)
+        };
+        ($check:ident($len:ident, $rng:ident)) => {
+            each_backend!(@each $check($len, $rng): B32, B64, B128, Bsize, Blong, BAuto)
+        };
+        (@each $check:ident($len:ident): $($flag_ls:ty),*) => {
+            $(for $len in lengths::<$flag_ls>() {
+                $check::<$flag_ls>($len);
+            })*
+        };
+        (@each $check:ident($len:ident, $rng:ident): $($flag_ls:ty),*) => {
+            $(for $len in lengths::<$flag_ls>() {
+                let $rng = &mut SplitMix64::new($len as u64);
+                $check::<$flag_ls>($len, $rng);
+            })*
+        };
+    }
     #[test]
     fn len() {
         let flag_ls1 = B32::from_iter(vec![true, false, false]);
@@ -346,6 +410,1189 @@ mod tests {
         }
     }
     #[test]
+    fn auto_promote() {
+        let mut flag_ls = BAuto::default();
+        for i in 0..200 {
+            flag_ls.push(i % 3 == 0);
+        }
+        assert!(matches!(flag_ls, BAuto::Blong(_)));
+        assert_eq!(flag_ls, BAuto::from_iter((0..200).map(|i| i % 3 == 0)));
+        assert!(flag_ls.remove(0));
+        flag_ls.insert(64, true);
+        assert_eq!(flag_ls.get(64), Some(true));
+        assert_eq!(flag_ls.len(), 200);
+
+        let mut flag_ls = BAuto::all_true(64);
+        flag_ls.insert(10, false);
+        assert!(matches!(flag_ls, BAuto::B128(_)));
+        assert_eq!(flag_ls.iter().filter(|f| !f).count(), 1);
+        assert!(!flag_ls[10]);
+
+        let mut flag_ls = BAuto::default();
+        flag_ls.set_len(129);
+        assert!(matches!(flag_ls, BAuto::Blong(_)));
+        flag_ls.set_len(20);
+        flag_ls.shrink_to_fit();
+        assert!(matches!(flag_ls, BAuto::B32(_)));
+        assert_eq!(flag_ls, BAuto::all_false(20));
+    }
+    #[test]
+    fn auto_ops() {
+        let small = BAuto::from(B32::from_iter(vec![true, true, false]));
+        let big = BAuto::from(B128::from_iter((0..100).map(|i| i < 2)));
+        let res = small.clone() & big.clone();
+        assert!(matches!(res, BAuto::B128(_)));
+        assert_eq!(res, BAuto::from_iter((0..100).map(|i| i < 2)));
+        let res = small.clone() | big.clone();
+        assert_eq!(res, BAuto::from_iter((0..100).map(|i| i < 2)));
+        let res = small.clone() ^ big.clone();
+        assert_eq!(res, BAuto::from_iter((0..100).map(|_| false)));
+        let res = big - small;
+        assert_eq!(res, BAuto::all_false(100));
+        assert_eq!(!BAuto::all_false(5), BAuto::all_true(5));
+    }
+    #[test]
+    fn schema() -> Result<(), SchemaError> {
+        let mut schema = FlagSchema::new();
+        schema.add_flag("fast_path", 0)?;
+        schema.add_flag("metrics", 3)?;
+        schema.add_flag("legacy", 1)?;
+        schema.add_alias("stats", "metrics")?;
+        schema.add_group("modern", &["fast_path", "stats"])?;
+        assert_eq!(schema.len(), 4);
+        assert_eq!(schema.add_flag("metrics", 5), Err(SchemaError::DuplicateName { name: "metrics".to_owned() }));
+        assert_eq!(schema.add_flag("other", 0), Err(SchemaError::DuplicateName { name: "fast_path".to_owned() }));
+        assert!(matches!(schema.add_flag("-bad", 6), Err(SchemaError::InvalidName { .. })));
+        assert!(matches!(schema.add_alias("x", "nope"), Err(SchemaError::UnknownName { .. })));
+
+        let flags: Blong = schema.parse(" modern , legacy,-stats")?;
+        assert_eq!(flags, Blong::from_iter(vec![true, true, false, false]));
+        assert_eq!(schema.format(&flags), "fast_path,legacy");
+        let flags: B32 = schema.parse("")?;
+        assert_eq!(flags, B32::all_false(4));
+
+        let mut flags = B64::all_true(2);
+        assert_eq!(schema.apply(&mut flags, "metrics,turbo"), Err(SchemaError::UnknownName { name: "turbo".to_owned() }));
+        assert_eq!(flags, B64::all_true(2));
+
+        schema.add_flag("far", 40)?;
+        assert!(matches!(schema.parse::<B32>("far"), Err(SchemaError::Flags(FlagLsError::MaximumLengthExceeded { .. }))));
+        assert_eq!(schema.format(&schema.parse::<B64>("far,+metrics")?), "metrics,far");
+        Ok(())
+    }
+    fn pseudo_random_matrix(rows: usize, cols: usize, seed: usize) -> BMatrix {
+        let mut m = BMatrix::new(rows, cols);
+        for r in 0..rows {
+            for c in 0..cols {
+                m.set(r, c, (r * 31 + c * 17 + seed) % 7 < 2);
+            }
+        }
+        m
+    }
+    #[test]
+    fn matrix_transpose() {
+        for (rows, cols) in [(0, 3), (1, 1), (3, 70), (70, 3), (65, 130), (200, 129)] {
+            let m = pseudo_random_matrix(rows, cols, 3);
+            let t = m.transpose();
+            assert_eq!((t.rows(), t.cols()), (cols, rows));
+            for r in 0..rows {
+                for c in 0..cols {
+                    assert_eq!(m.get(r, c), t.get(c, r));
+                }
+            }
+            assert_eq!(t.transpose(), m);
+            for c in 0..cols {
+                assert_eq!(m.column(c), t.row_to_blong(c));
+            }
+        }
+    }
+    #[test]
+    fn matrix_multiply() {
+        let a = pseudo_random_matrix(5, 70, 1);
+        let b = pseudo_random_matrix(70, 9, 4);
+        let c = &a * &b;
+        for i in 0..5 {
+            for j in 0..9 {
+                let expected = (0..70).any(|k| a.get(i, k) == Some(true) && b.get(k, j) == Some(true));
+                assert_eq!(c.get(i, j), Some(expected));
+            }
+        }
+        assert_eq!(BMatrix::identity(70).multiply(&b), b);
+    }
+    #[test]
+    fn matrix_closure() {
+        let mut chain = BMatrix::new(100, 100);
+        for i in 0..99 {
+            chain.set(i, i + 1, true);
+        }
+        let reach = chain.transitive_closure();
+        for i in 0..100 {
+            for j in 0..100 {
+                assert_eq!(reach.get(i, j), Some(i < j));
+            }
+        }
+        chain.set(99, 0, true);
+        let reach = chain.transitive_closure();
+        assert!((0..100).all(|i| reach.row(i).count_ones() == 100));
+    }
+    #[test]
+    fn matrix_rows() {
+        let rows = vec![Blong::from_iter(vec![true, false]), Blong::all_true(70), Blong::default()];
+        let m = BMatrix::from(rows);
+        assert_eq!((m.rows(), m.cols()), (3, 70));
+        assert_eq!(m.get(0, 0), Some(true));
+        assert_eq!(m.get(0, 1), Some(false));
+        assert_eq!(m.get(0, 70), None);
+        assert_eq!(format!("{m:?}"), format!("BMatrix(3x70)[\n10{}\n{}\n{}\n]", "0".repeat(68), "1".repeat(70), "0".repeat(70)));
+        assert_eq!(format!("{:?}", BMatrix::default()), "BMatrix(0x0)[\n]");
+        let back: Vec<Blong> = m.into();
+        let mut first = Blong::all_false(70);
+        first.set(0, true);
+        assert_eq!(back, vec![first, Blong::all_true(70), Blong::all_false(70)]);
+    }
+    #[test]
+    fn gf2() {
+        let a = pseudo_random_matrix(40, 90, 2);
+        let rank = a.rank();
+        assert!(rank <= 40);
+        assert_eq!(a.transpose().rank(), rank);
+        let basis = a.null_space();
+        assert_eq!(basis.len(), 90 - rank);
+        for v in &basis {
+            let column = BMatrix::from(vec![v.clone()]).transpose();
+            assert_eq!(a.gf2_multiply(&column), BMatrix::new(40, 1));
+        }
+        let x = Blong::from_iter((0..90).map(|i| i % 5 == 1));
+        let b = a.gf2_multiply(&BMatrix::from(vec![x]).transpose()).column(0);
+        let solution = a.solve(&b).expect("b was built from a solution");
+        assert_eq!(a.gf2_multiply(&BMatrix::from(vec![solution]).transpose()).column(0), b);
+
+        let mut singular = BMatrix::identity(3);
+        singular.set(2, 2, false);
+        assert_eq!(singular.inverse(), None);
+        assert_eq!(singular.solve(&Blong::from_iter(vec![false, false, true])), None);
+        assert_eq!(BMatrix::new(0, 0).inverse(), Some(BMatrix::new(0, 0)));
+
+        let mut square = pseudo_random_matrix(70, 70, 5);
+        for i in 0..70 {
+            square.set(i, i, true);
+            for j in 0..i {
+                square.set(i, j, false);
+            }
+        }
+        let inv = square.inverse().expect("unit upper triangular matrices are invertible");
+        assert_eq!(square.gf2_multiply(&inv), BMatrix::identity(70));
+        assert_eq!(inv.gf2_multiply(&square), BMatrix::identity(70));
+    }
+    #[test]
+    fn bloom() {
+        let mut filter = BloomFilter::with_rate(1000, 0.01);
+        assert_eq!(filter.hash_count(), 7);
+        for i in 0..1000 {
+            filter.insert(&i);
+        }
+        assert!((0..1000).all(|i| filter.contains(&i)));
+        let false_positives = (1000..11000).filter(|i| filter.contains(i)).count();
+        assert!(false_positives < 300, "{false_positives} false positives");
+
+        let mut a = BloomFilter::new(512, 3);
+        let mut b = BloomFilter::new(512, 3);
+        a.insert("left");
+        a.insert("both");
+        b.insert("right");
+        b.insert("both");
+        let mut union = a.clone();
+        union.union_with(&b);
+        assert!(union.contains("left") && union.contains("right") && union.contains("both"));
+        a.intersect_with(&b);
+        assert!(a.contains("both"));
+        a.clear();
+        assert!(!a.contains("both"));
+        assert_eq!(a.as_flags(), &Blong::all_false(512));
+
+        let mut counting = CountingBloomFilter::new(512, 3);
+        counting.insert("kept");
+        counting.insert("gone");
+        assert!(counting.remove("gone"));
+        assert!(!counting.remove("gone"));
+        assert!(counting.contains("kept"));
+        assert!(!counting.contains("gone"));
+        let plain = counting.to_bloom_filter();
+        assert!(plain.contains("kept"));
+        assert_eq!(plain.bit_count(), counting.counter_count());
+    }
+    #[cfg(feature = "serde")]
+    #[test]
+    fn bloom_serde() -> Result<(), serde_json::Error> {
+        let mut filter = BloomFilter::new(64, 3);
+        filter.insert("apple");
+        // the positions are pinned, so a filter saved by one build is read correctly by any other
+        assert_eq!(filter.as_flags().iter().enumerate().filter(|(_, flag)| *flag).map(|(idx, _)| idx).collect::<Vec<_>>(), [16, 27, 38]);
+        let mut filter = BloomFilter::with_rate(200, 0.01);
+        for i in 0..200_u64 {
+            filter.insert(&i);
+        }
+        let loaded: BloomFilter = serde_json::from_str(&serde_json::to_string(&filter)?)?;
+        assert_eq!(loaded.as_flags(), filter.as_flags());
+        assert!((0..200_u64).all(|i| loaded.contains(&i)));
+        let mut counting = CountingBloomFilter::new(256, 3);
+        counting.insert("kept");
+        let mut loaded: CountingBloomFilter = serde_json::from_str(&serde_json::to_string(&counting)?)?;
+        assert!(loaded.contains("kept"));
+        assert!(loaded.remove("kept"));
+        assert!(!loaded.contains("kept"));
+        Ok(())
+    }
+    #[cfg(feature = "serde")]
+    #[test]
+    fn matrix_serde() -> Result<(), serde_json::Error> {
+        let mut matrix = BMatrix::new(3, 70);
+        matrix.set(2, 69, true);
+        let loaded: BMatrix = serde_json::from_str(&serde_json::to_string(&matrix)?)?;
+        assert_eq!(loaded, matrix);
+        assert!(serde_json::from_str::<BMatrix>(r#"{"inner":[],"rows":5,"cols":5}"#).is_err());
+        assert!(serde_json::from_str::<BMatrix>(r#"{"inner":[0,0,0],"rows":3,"cols":70}"#).is_err());
+        assert!(serde_json::from_str::<BMatrix>(r#"{"inner":[],"rows":0,"cols":5}"#).is_ok());
+        Ok(())
+    }
+    #[test]
+    fn id_allocator() -> Result<(), FlagLsError> {
+        let mut ids: IdAllocator<B32> = IdAllocator::new();
+        for i in 0..32 {
+            assert_eq!(ids.allocate()?, i);
+        }
+        assert_eq!(ids.allocate(), Err(FlagLsError::MaximumLengthExceeded { mx_len: 32, attempt_len: 33 }));
+        assert!(ids.free(31));
+        assert!(!ids.free(31));
+        assert!(!ids.free(40));
+        assert_eq!(ids.allocate()?, 31);
+        assert_eq!(ids.len(), 32);
+
+        let mut ids: IdAllocator<Blong> = IdAllocator::new();
+        assert!(ids.is_empty());
+        assert_eq!(ids.allocate_run(100)?, 0);
+        assert_eq!(ids.allocate()?, 100);
+        for id in 10..20 {
+            ids.free(id);
+        }
+        assert_eq!(ids.allocate_run(11)?, 101);
+        assert_eq!(ids.allocate_run(10)?, 10);
+        assert_eq!(ids.allocate_run(0)?, 0);
+        ids.free(5);
+        assert!(!ids.is_allocated(5));
+        assert!(ids.is_allocated(111));
+        assert_eq!(ids.iter().count(), 111);
+        assert_eq!(ids.iter().take(6).collect::<Vec<_>>(), vec![0, 1, 2, 3, 4, 6]);
+        ids.clear();
+        assert_eq!(ids.allocate()?, 0);
+        assert_eq!(ids.allocate_run(299)?, 1);
+        ids.free(200);
+        ids.free(130);
+        assert_eq!(ids.len(), 298);
+        assert_eq!(ids.allocate()?, 130);
+        assert_eq!(ids.allocate()?, 200);
+        assert_eq!(ids.allocate()?, 300);
+        assert_eq!(ids.len(), 301);
+        Ok(())
+    }
+    #[test]
+    fn bitstream() -> Result<(), FlagLsError> {
+        let mut writer = BitWriter::new();
+        for i in 0..20_u64 {
+            writer.write_bits(i * 7 % 8, 3);
+        }
+        writer.write_bits(u64::MAX, 64);
+        writer.write_flags(&B32::from_iter(vec![true, false, true]));
+        writer.align_byte();
+        assert_eq!(writer.len() % 8, 0);
+        std::io::Write::write_all(&mut writer, b"hi").expect("writing to a BitWriter can not fail");
+        writer.write_bool(true);
+        let flags = writer.into_inner();
+
+        let mut reader = BitReader::new(&flags);
+        for i in 0..20_u64 {
+            assert_eq!(reader.read_bits(3)?, i * 7 % 8);
+        }
+        assert_eq!(reader.read_bits(64)?, u64::MAX);
+        assert_eq!(reader.read_flags::<B64>(3)?, B64::from_iter(vec![true, false, true]));
+        reader.align_byte()?;
+        let mut bytes = Vec::new();
+        std::io::Read::read_to_end(&mut reader, &mut bytes).expect("reading from a BitReader can not fail");
+        assert_eq!(bytes, b"hi");
+        assert_eq!(reader.remaining(), 1);
+        let pos = reader.position();
+        assert_eq!(reader.read_bits(2), Err(FlagLsError::IndexOutOfBounds { idx: pos + 1, len: flags.len() }));
+        assert_eq!(reader.position(), pos);
+        assert!(reader.read_bool()?);
+        assert!(reader.align_to(64).is_err());
+        reader.seek(60)?;
+        assert_eq!(reader.read_bits(4)?, 0xF);
+        assert!(reader.seek(flags.len() + 1).is_err());
+        Ok(())
+    }
+    #[test]
+    fn bit_fields() -> Result<(), FlagLsError> {
+        fn check<T: FlagLs>(len: usize) -> Result<(), FlagLsError> {
+            let mut flags = T::all_false(len);
+            let mut expected = vec![false; len];
+            for (step, start) in (0..len).step_by(3).enumerate() {
+                let end = (start + step % 70 + 1).min(len);
+                let value = 0x0123_4567_89AB_CDEF_FEDC_BA98_7654_3210_u128.rotate_left(u32::try_from(step).expect("small"));
+                flags.try_set_bits(start..end, value)?;
+                for (shift, flag) in expected[start..end].iter_mut().enumerate() {
+                    *flag = (value >> shift) & 1 == 1;
+                }
+                assert!(flags.iter().eq(expected.iter().copied()));
+                let read = expected[start..end].iter().rev().fold(0, |out, flag| (out << 1) | u128::from(*flag));
+                assert_eq!(flags.get_bits(start..end), read);
+            }
+            assert_eq!(flags.get_bits(len..len), 0);
+            assert_eq!(flags.try_get_bits(0..len + 1), Err(FlagLsError::IndexOutOfBounds { idx: len, len }));
+            assert_eq!(flags.try_set_bits(len..len + 2, 0), Err(FlagLsError::IndexOutOfBounds { idx: len + 1, len }));
+            assert!(flags.iter().eq(expected.iter().copied()));
+            Ok(())
+        }
+        check::<B32>(32)?;
+        check::<B64>(64)?;
+        check::<B128>(128)?;
+        check::<Bsize>(usize::BITS as usize)?;
+        check::<Blong>(300)?;
+        check::<BAuto>(200)?;
+        let flags = Blong::all_true(200);
+        assert_eq!(flags.get_bits(10..138), u128::MAX);
+        assert_eq!(flags.try_get_bits(0..129), Err(FlagLsError::MaximumLengthExceeded { mx_len: 128, attempt_len: 129 }));
+        Ok(())
+    }
+    #[test]
+    fn similarity() {
+        fn pattern(len: usize, seed: usize) -> Vec<bool> {
+            (0..len).map(|idx| (idx * 7 + seed) % 5 < 2 || idx % 11 == seed % 11).collect()
+        }
+        #[allow(clippy::cast_precision_loss)]
+        fn check_pair<T: FlagLs>(l_len: usize, r_len: usize) {
+            let (l, r) = (pattern(l_len, 1), pattern(r_len, 3));
+            let padded = |v: &Vec<bool>, idx: usize| v.get(idx).copied().unwrap_or(false);
+            let both = (0..l_len.max(r_len)).filter(|idx| padded(&l, *idx) && padded(&r, *idx)).count();
+            let either = (0..l_len.max(r_len)).filter(|idx| padded(&l, *idx) || padded(&r, *idx)).count();
+            let (l_count, r_count) = (l.iter().filter(|f| **f).count(), r.iter().filter(|f| **f).count());
+            let (l_flags, r_flags) = (T::from_iter(l), T::from_iter(r));
+            assert_eq!(l_flags.and_count(&r_flags), both);
+            assert_eq!(r_flags.and_count(&l_flags), both);
+            assert_eq!(l_flags.or_count(&r_flags), either);
+            assert_eq!(l_flags.xor_count(&r_flags), either - both);
+            assert_eq!(l_flags.hamming_distance(&r_flags), either - both);
+            // lists with no true flags are identical to each other, and share nothing with any other list
+            let ratio = |num: f64, den: f64| if den == 0.0 { f64::from(u8::from(num == 0.0 && l_count + r_count == 0)) } else { num / den };
+            assert!((l_flags.jaccard(&r_flags) - ratio(both as f64, either as f64)).abs() < 1e-9);
+            assert!((l_flags.dice(&r_flags) - ratio(2.0 * both as f64, (l_count + r_count) as f64)).abs() < 1e-9);
+            assert!((l_flags.cosine(&r_flags) - ratio(both as f64, ((l_count * r_count) as f64).sqrt())).abs() < 1e-9);
+            assert!((l_flags.jaccard(&l_flags) - 1.0).abs() < 1e-9);
+            assert!((l_flags.cosine(&T::default()) - f64::from(u8::from(l_count == 0))).abs() < 1e-9);
+            assert!((T::default().dice(&T::default()) - 1.0).abs() < 1e-9);
+        }
+        fn check<T: FlagLs>(len: usize) {
+            check_pair::<T>(len, len * 2 / 3);
+            check_pair::<T>(len * 2 / 3, len);
+        }
+        each_backend!(check(len));
+    }
+    #[test]
+    fn random_flags() {
+        fn check<T: FlagLs>(len: usize, rng: &mut SplitMix64) {
+            assert!(T::random(len, 0.0, || rng.next_u64()).iter().eq(T::all_false(len).iter()));
+            assert!(T::random(len, 1.0, || rng.next_u64()).iter().eq(T::all_true(len).iter()));
+            let mut hits = vec![0_usize; len];
+            let mut sizes = vec![0, 1, len / 2, len];
+            sizes.sort_unstable();
+            sizes.dedup();
+            for k in sizes.into_iter().filter(|k| *k <= len) {
+                let flags = T::choose(len, k, || rng.next_u64());
+                assert_eq!(flags.len(), len);
+                assert_eq!(flags.iter().filter(|flag| *flag).count(), k);
+                if k == 1 {
+                    hits[flags.sample(|| rng.next_u64()).expect("one flag is set")] += 1;
+                }
+            }
+            let mut flags = T::choose(len, len / 3, || rng.next_u64());
+            let before = flags.iter().filter(|flag| *flag).count();
+            flags.shuffle(|| rng.next_u64());
+            assert_eq!(flags.iter().filter(|flag| *flag).count(), before);
+            for _ in 0..20 {
+                let idx = flags.sample(|| rng.next_u64());
+                assert!(idx.is_none_or(|idx| flags.get(idx) == Some(true)));
+            }
+            assert_eq!(hits.iter().sum::<usize>(), usize::from(len > 0));
+        }
+        each_backend!(check(len, rng));
+        // every position should be picked roughly as often as every other
+        let mut rng = SplitMix64::new(1);
+        let mut counts = [0_usize; 10];
+        for _ in 0..10_000 {
+            counts[B32::all_true(10).sample(|| rng.next_u64()).expect("all flags are set")] += 1;
+        }
+        assert!(counts.iter().all(|count| (800..1200).contains(count)));
+        let mut counts = [0_usize; 10];
+        for _ in 0..10_000 {
+            let mut flags = B32::from_iter(vec![true]);
+            flags.set_len(10);
+            flags.shuffle(|| rng.next_u64());
+            counts[flags.iter().position(|flag| flag).expect("one flag is set")] += 1;
+        }
+        assert!(counts.iter().all(|count| (800..1200).contains(count)));
+    }
+    #[test]
+    fn blong_capacity() -> Result<(), FlagLsError> {
+        let mut flags = Blong::with_capacity(130);
+        assert!(flags.capacity() >= 130);
+        flags.try_set_len(130)?;
+        flags.set(129, true);
+        flags.try_reserve(1000)?;
+        assert!(flags.capacity() >= 1130);
+        flags.try_set_len(64)?;
+        flags.shrink_to_fit();
+        assert_eq!(flags, Blong::all_false(64));
+        assert_eq!(flags.try_reserve(usize::MAX), Err(FlagLsError::MaximumLengthExceeded { mx_len: Blong::MAX_LENGTH, attempt_len: usize::MAX }));
+        assert!(matches!(flags.try_set_len(usize::MAX - 1), Err(FlagLsError::AllocationFailed { .. })));
+        assert_eq!(flags, Blong::all_false(64));
+        assert_eq!(FlagLsError::AllocationFailed { attempt_len: 5 }.to_string(), "failed to allocate space for a flag list of length 5");
+        assert_eq!(FlagLsError::InvalidRange { start: 3, end: 1 }.to_string(), "range 3..1 starts after it ends");
+        Ok(())
+    }
+    #[test]
+    fn flag_views() -> Result<(), FlagLsError> {
+        let bytes: Vec<u8> = (0..24_u8).map(|i| i.wrapping_mul(37) ^ 0x5A).collect();
+        let words32: Vec<u32> = bytes.chunks(4).map(|c| u32::from_le_bytes(c.try_into().expect("chunks of 4"))).collect();
+        let words64: Vec<u64> = bytes.chunks(8).map(|c| u64::from_le_bytes(c.try_into().expect("chunks of 8"))).collect();
+        let expected: Vec<bool> = bytes.iter().flat_map(|byte| (0..8).map(move |i| (byte >> i) & 1 == 1)).collect();
+        for len in [0, 1, 63, 64, 100, 192] {
+            let views = [
+                FlagView::from_bytes(&bytes, len, BitOrder::Lsb0)?,
+                FlagView::from_u32s(&words32, len, BitOrder::Lsb0)?,
+                FlagView::from_u64s(&words64, len, BitOrder::Lsb0)?,
+            ];
+            for view in views {
+                assert!(view.iter().eq(expected[..len].iter().copied()));
+                assert_eq!(view.count_ones(), expected[..len].iter().filter(|flag| **flag).count());
+                assert!(view.iter_ones().eq((0..len).filter(|idx| expected[*idx])));
+                assert_eq!(view.get(len), None);
+                assert_eq!(Blong::from(view), Blong::from_iter(expected[..len].iter().copied()));
+            }
+        }
+        let reversed: Vec<u8> = bytes.iter().map(|byte| byte.reverse_bits()).collect();
+        let msb = FlagView::from_bytes(&reversed, 100, BitOrder::Msb0)?;
+        assert!(msb.iter().eq(expected[..100].iter().copied()));
+        assert_eq!(Blong::from(msb), Blong::from_iter(expected[..100].iter().copied()));
+        assert_eq!(FlagView::from_u32s(&words32, 193, BitOrder::Lsb0).err(), Some(FlagLsError::MaximumLengthExceeded { mx_len: 192, attempt_len: 193 }));
+
+        let mut buffer = [0xFF_u8; 3];
+        let mut view = FlagViewMut::from_bytes(&mut buffer, 12, BitOrder::Msb0)?;
+        for idx in (0..12).step_by(2) {
+            assert_eq!(view.try_set(idx, false), Some(true));
+        }
+        assert_eq!(view.count_ones(), 6);
+        assert_eq!(view.try_set(12, false), None);
+        assert_eq!(buffer, [0b0101_0101, 0b0101_1111, 0xFF]);
+        let mut words = [0_u64; 2];
+        let mut view = FlagViewMut::from_u64s(&mut words, 128, BitOrder::Lsb0)?;
+        view.set(127, true);
+        view.set(3, true);
+        assert_eq!(view.as_view().iter_ones().collect::<Vec<_>>(), vec![3, 127]);
+        assert_eq!(words, [8, 1 << 63]);
+        Ok(())
+    }
+    #[test]
+    fn bitmap_files() -> Result<(), FormatError> {
+        fn round_trip<T: FlagLs>(len: usize) -> Result<(), FormatError> {
+            let flags = T::from_iter((0..len).map(|idx| idx % 7 == 0 || (idx / 50) % 2 == 1));
+            for encoding in [Encoding::Raw, Encoding::RunLength] {
+                let bytes = encode_bitmap(&flags, encoding);
+                let file = BitmapFile::parse(&bytes)?;
+                assert_eq!((file.len(), file.encoding()), (len, encoding));
+                assert!(file.iter().eq(flags.iter()));
+                assert!(file.to_flags::<T>()?.iter().eq(flags.iter()));
+                assert!(T::read_from(bytes.as_slice())?.iter().eq(flags.iter()));
+            }
+            let mut bytes = Vec::new();
+            flags.write_to(&mut bytes)?;
+            let (raw, runs) = (encode_bitmap(&flags, Encoding::Raw), encode_bitmap(&flags, Encoding::RunLength));
+            assert_eq!(bytes, if runs.len() < raw.len() { runs } else { raw });
+            assert!(T::read_from(bytes.as_slice())?.iter().eq(flags.iter()));
+            Ok(())
+        }
+        for len in [0, 1, 8, 31, 32] {
+            round_trip::<B32>(len)?;
+            round_trip::<BAuto>(len)?;
+        }
+        round_trip::<B64>(64)?;
+        round_trip::<B128>(100)?;
+        round_trip::<Bsize>(20)?;
+        round_trip::<Blong>(1000)?;
+        round_trip::<Blong>(100_000)?;
+
+        let bytes = encode_bitmap(&Blong::all_true(40), Encoding::Raw);
+        assert!(matches!(BitmapFile::parse(&bytes)?.to_flags::<B32>(), Err(FormatError::Flags(FlagLsError::MaximumLengthExceeded { mx_len: 32, attempt_len: 40 }))));
+        assert!(matches!(BitmapFile::parse(&bytes[..bytes.len() - 1]), Err(FormatError::Truncated { expected: 33, found: 32 })));
+        assert!(matches!(Blong::read_from(&bytes[..10]), Err(FormatError::Truncated { expected: 24, found: 10 })));
+        assert!(matches!(Blong::read_from(&bytes[..30]), Err(FormatError::Truncated { expected: 33, found: 30 })));
+        let corrupt = |idx: usize, value: u8| {
+            let mut bytes = bytes.clone();
+            bytes[idx] = value;
+            bytes
+        };
+        assert!(matches!(BitmapFile::parse(&corrupt(0, b'X')), Err(FormatError::BadMagic)));
+        assert!(matches!(BitmapFile::parse(&corrupt(4, 2)), Err(FormatError::UnsupportedVersion { version: 2 })));
+        assert!(matches!(BitmapFile::parse(&corrupt(5, 9)), Err(FormatError::UnknownEncoding { encoding: 9 })));
+        assert!(matches!(BitmapFile::parse(&corrupt(26, 0)), Err(FormatError::ChecksumMismatch { .. })));
+        let mut long = bytes.clone();
+        long.push(0);
+        assert!(matches!(BitmapFile::parse(&long), Err(FormatError::Corrupt { .. })));
+        assert!(BitmapFile::parse(&bytes)?.view().is_some());
+        assert!(BitmapFile::parse(&encode_bitmap(&Blong::all_true(40), Encoding::RunLength))?.view().is_none());
+        Ok(())
+    }
+    #[test]
+    fn structural_edits() {
+        fn check<T: FlagLs>(len: usize, rng: &mut SplitMix64) {
+            for density in [0.0, 0.1, 0.5, 0.9, 1.0] {
+                let flags = T::random(len, density, || rng.next_u64());
+                let expected: Vec<bool> = flags.iter().collect();
+
+                let mut reversed = T::from_iter(expected.iter().copied());
+                reversed.reverse();
+                assert!(reversed.iter().eq(expected.iter().rev().copied()));
+
+                let mut swapped = T::from_iter(expected.iter().copied());
+                let mut swapped_expected = expected.clone();
+                for idx in 0..len {
+                    let other = (idx * 13 + 5) % len;
+                    swapped.swap(idx, other);
+                    swapped_expected.swap(idx, other);
+                }
+                assert!(swapped.iter().eq(swapped_expected.iter().copied()));
+
+                let mut retained = T::from_iter(expected.iter().copied());
+                retained.retain(|idx, flag| flag != (idx % 3 == 0));
+                let retained_expected = expected.iter().enumerate().filter(|(idx, flag)| **flag != (idx % 3 == 0)).map(|(_, flag)| *flag);
+                assert!(retained.iter().eq(retained_expected));
+
+                let mut partitioned = T::from_iter(expected.iter().copied());
+                let count = expected.iter().filter(|flag| **flag).count();
+                assert_eq!(partitioned.stable_partition(), count);
+                assert!(partitioned.iter().eq((0..len).map(|idx| idx < count)));
+
+                let mut deduped = T::from_iter(expected.iter().copied());
+                deduped.dedup();
+                let mut dedup_expected = expected.clone();
+                dedup_expected.dedup();
+                assert!(deduped.iter().eq(dedup_expected.iter().copied()));
+                // the padding past the end must stay clear, so growing again gives false flags
+                if dedup_expected.len() < T::MAX_LENGTH {
+                    deduped.set_len(dedup_expected.len() + 1);
+                    assert_eq!(deduped.get(dedup_expected.len()), Some(false));
+                }
+            }
+        }
+        each_backend!(check(len, rng));
+    }
+    #[test]
+    fn concat_and_split() {
+        fn check<T: FlagLs>(len: usize, rng: &mut SplitMix64) {
+            let flags = T::random(len, 0.5, || rng.next_u64());
+            let expected: Vec<bool> = flags.iter().collect();
+            for at in [0, 1.min(len), len / 3, len / 2, len.saturating_sub(1), len] {
+                let (head, tail) = T::from_iter(expected.iter().copied()).split_at(at);
+                assert!(head.iter().eq(expected[..at].iter().copied()));
+                assert!(tail.iter().eq(expected[at..].iter().copied()));
+                let (mut head, mut tail) = (head, tail);
+                head.append(&mut tail);
+                assert!(tail.is_empty());
+                assert!(head.iter().eq(expected.iter().copied()));
+
+                let end = (at + 70).min(len);
+                let mut drained = T::from_iter(expected.iter().copied());
+                let removed = drained.drain(at..end);
+                assert!(removed.iter().eq(expected[at..end].iter().copied()));
+                assert!(drained.iter().eq(expected[..at].iter().chain(&expected[end..]).copied()));
+
+                let mut spliced = T::from_iter(expected.iter().copied());
+                let end = (at + 3).min(len);
+                let mut insert = vec![true, false, true];
+                // a full fixed width list can only take as many flags as the splice removes
+                insert.truncate(T::MAX_LENGTH - len + (end - at));
+                let mut spliced_expected = expected.clone();
+                let removed_expected: Vec<bool> = spliced_expected.splice(at..end, insert.iter().copied()).collect();
+                let removed = spliced.splice(at..end, insert);
+                assert!(removed.iter().eq(removed_expected));
+                assert!(spliced.iter().eq(spliced_expected.iter().copied()));
+            }
+        }
+        each_backend!(check(len, rng));
+
+        let mut full = B32::all_true(16);
+        full.append(&mut B32::all_true(16));
+        assert_eq!(full, B32::all_true(32));
+        let header = B32::from_iter(vec![true, false, true]);
+        let payload = B64::all_true(60);
+        let joined: B128 = header.concat(&payload);
+        assert_eq!(joined.split_at(3), (B128::from(header), B128::from_iter(payload.iter())));
+        let joined: Blong = B128::all_true(128).concat(&B128::all_false(100));
+        assert_eq!(joined.iter().filter(|flag| *flag).count(), 128);
+        assert_eq!(joined.len(), 228);
+        let joined = Blong::all_true(70).concat(&Blong::all_true(70));
+        assert_eq!(joined, Blong::all_true(140));
+    }
+    #[test]
+    fn interleaving() {
+        let mut rng = SplitMix64::new(43);
+        for _ in 0..200 {
+            let word = rng.next_u64();
+            let (col, row) = (u32::try_from(word >> 32).expect("high half"), u32::try_from(word & 0xFFFF_FFFF).expect("low half"));
+            let code = morton_encode_2d(col, row);
+            assert!((0..32).all(|i| (code >> (2 * i)) & 1 == u64::from((col >> i) & 1) && (code >> (2 * i + 1)) & 1 == u64::from((row >> i) & 1)));
+            assert_eq!(morton_decode_2d(code), (col, row));
+            let (col, row, depth) = (col & 0x1F_FFFF, row & 0x1F_FFFF, (col >> 11) & 0x1F_FFFF);
+            let code = morton_encode_3d(col, row, depth);
+            assert!((0..21).all(|i| (code >> (3 * i)) & 1 == u64::from((col >> i) & 1) && (code >> (3 * i + 2)) & 1 == u64::from((depth >> i) & 1)));
+            assert_eq!(morton_decode_3d(code), (col, row, depth));
+
+            let len = usize::try_from(word % 33).expect("small");
+            let evens = B32::random(len, 0.5, || rng.next_u64());
+            let odds = B32::random(len, 0.5, || rng.next_u64());
+            let mixed = B64::interleave(evens, odds);
+            assert!(mixed.iter().eq(evens.iter().zip(odds.iter()).flat_map(<[bool; 2]>::from)));
+            assert_eq!(mixed.deinterleave(), (evens, odds));
+            let wide = B128::interleave(B64::try_from_iter(evens.iter().chain(odds.iter())).expect("fits"), B64::all_true(2 * len));
+            assert_eq!(wide.len(), 4 * len);
+            let (even, odd) = wide.deinterleave();
+            assert!(even.iter().eq(evens.iter().chain(odds.iter())));
+            assert_eq!(odd, B64::all_true(2 * len));
+        }
+        let mixed = B64::interleave(B32::all_true(3), B32::all_true(1));
+        assert_eq!(mixed, B64::from_iter(vec![true, true, true, false, true, false]));
+        assert_eq!(B64::from_iter(vec![true, false, true]).deinterleave(), (B32::all_true(2), B32::all_false(1)));
+        let lists: Vec<Blong> = (0..5).map(|way| Blong::random(100 + way, 0.5, || rng.next_u64())).collect();
+        let mixed = Blong::interleave(&lists);
+        assert_eq!(mixed.len(), 5 * 104);
+        for (way, list) in mixed.deinterleave(5).iter().enumerate() {
+            assert!(list.iter().take(lists[way].len()).eq(lists[way].iter()));
+            assert!(list.iter().skip(lists[way].len()).all(|flag| !flag));
+        }
+        assert!(Blong::interleave(&[]).is_empty());
+        for (l_len, r_len) in [(0, 0), (1, 0), (64, 64), (300, 130), (65, 200)] {
+            let pair = [Blong::random(l_len, 0.5, || rng.next_u64()), Blong::random(r_len, 0.5, || rng.next_u64())];
+            let padded = |list: &Blong, idx: usize| list.get(idx) == Some(true);
+            let mixed = Blong::interleave(&pair);
+            assert!(mixed.iter().eq((0..l_len.max(r_len)).flat_map(|idx| [padded(&pair[0], idx), padded(&pair[1], idx)])));
+            let split = mixed.deinterleave(2);
+            assert!(split.iter().zip(&pair).all(|(list, original)| list.len() == l_len.max(r_len) && list.iter().take(original.len()).eq(original.iter())));
+            let odd = Blong::from_iter(mixed.iter().skip(1));
+            assert_eq!(odd.deinterleave(2), vec![split[1].clone(), Blong::from_iter(split[0].iter().skip(1))]);
+        }
+    }
+    #[test]
+    fn conversion_matrix() -> Result<(), FlagLsError> {
+        fn check<S: FlagLs, T: FlagLs>(rng: &mut SplitMix64) {
+            for len in [0, 1, 31, 32, 33, 63, 64, 65, 127, 128, 129, 200] {
+                if len > S::MAX_LENGTH {
+                    continue;
+                }
+                let src = S::random(len, 0.5, || rng.next_u64());
+                match src.convert::<T>() {
+                    Ok(out) => assert!(out.iter().eq(src.iter())),
+                    Err(err) => {
+                        assert!(len > T::MAX_LENGTH);
+                        assert_eq!(err, FlagLsError::MaximumLengthExceeded { mx_len: T::MAX_LENGTH, attempt_len: len });
+                    }
+                }
+            }
+        }
+        let mut rng = SplitMix64::new(44);
+        check::<B32, B32>(&mut rng);
+        check::<B32, Blong>(&mut rng);
+        check::<B64, B32>(&mut rng);
+        check::<B128, Bsize>(&mut rng);
+        check::<Bsize, B128>(&mut rng);
+        check::<Blong, B64>(&mut rng);
+        check::<Blong, BAuto>(&mut rng);
+        check::<BAuto, B128>(&mut rng);
+
+        let small = B32::random(32, 0.5, || rng.next_u64());
+        let wide = B64::random(64, 0.5, || rng.next_u64());
+        assert_eq!(B128::from(wide), wide.convert()?);
+        assert_eq!(B128::from(Bsize::all_true(7)), B128::all_true(7));
+        assert_eq!(B64::from(Bsize::all_true(usize::BITS as usize)), B64::all_true(usize::BITS as usize));
+        assert_eq!(Bsize::from(small), small.convert()?);
+        assert_eq!(Blong::from(small), small.convert()?);
+        assert!(matches!(BAuto::from(Bsize::all_true(3)), BAuto::B32(_)));
+        assert_eq!(B32::try_from(BAuto::from(wide)), Err(FlagLsError::MaximumLengthExceeded { mx_len: 32, attempt_len: 64 }));
+        assert_eq!(B64::try_from(BAuto::from(wide))?, wide);
+        assert_eq!(Bsize::try_from(BAuto::all_true(129)), Err(FlagLsError::MaximumLengthExceeded { mx_len: Bsize::MAX_LENGTH, attempt_len: 129 }));
+        assert_eq!(B128::try_from(BAuto::from(small))?, B128::from(small));
+        Ok(())
+    }
+    #[test]
+    fn checked_operations() -> Result<(), FlagLsError> {
+        fn check<T: FlagLs + Clone>(len: usize) -> Result<(), FlagLsError> {
+            let mut flag_ls = T::try_all_true(len)?;
+            let before = flag_ls.clone();
+            let unchanged = |flag_ls: &T| flag_ls.iter().eq(before.iter());
+            assert_eq!(flag_ls.checked_get(len), Err(FlagLsError::IndexOutOfBounds { idx: len, len }));
+            assert_eq!(flag_ls.checked_set(len, false), Err(FlagLsError::IndexOutOfBounds { idx: len, len }));
+            assert_eq!(flag_ls.checked_remove(len), Err(FlagLsError::IndexOutOfBounds { idx: len, len }));
+            assert_eq!(flag_ls.try_swap(0, len), Err(FlagLsError::IndexOutOfBounds { idx: len, len }));
+            assert_eq!(flag_ls.try_split_off(len + 1).err(), Some(FlagLsError::IndexOutOfBounds { idx: len + 1, len }));
+            assert_eq!(flag_ls.try_drain(0..len + 1).err(), Some(FlagLsError::IndexOutOfBounds { idx: len, len }));
+            assert_eq!(flag_ls.try_drain(len..1).err(), Some(FlagLsError::InvalidRange { start: len, end: 1 }));
+            assert_eq!(flag_ls.try_splice(len..1, [true]).err(), Some(FlagLsError::InvalidRange { start: len, end: 1 }));
+            assert!(unchanged(&flag_ls));
+            assert_eq!(flag_ls.checked_set(len - 1, false), Ok(true));
+            assert_eq!(flag_ls.checked_get(len - 1), Ok(false));
+            assert_eq!(flag_ls.checked_remove(len - 1), Ok(false));
+            assert_eq!(flag_ls.checked_remove(0), Ok(true));
+            flag_ls.try_set_len(len)?;
+            assert_eq!(flag_ls.try_swap(0, len - 1), Ok(()));
+            assert_eq!(flag_ls.checked_get(0), Ok(false));
+            Ok(())
+        }
+        fn check_full<T: FlagLs + Clone>() {
+            let mut flag_ls = T::try_all_true(T::MAX_LENGTH).expect("fits");
+            let before = flag_ls.clone();
+            let overflow = FlagLsError::MaximumLengthExceeded { mx_len: T::MAX_LENGTH, attempt_len: T::MAX_LENGTH + 1 };
+            assert_eq!(T::try_all_false(T::MAX_LENGTH + 1).err(), Some(overflow));
+            assert_eq!(flag_ls.try_set_len(T::MAX_LENGTH + 1), Err(overflow));
+            assert_eq!(flag_ls.try_append(&mut T::all_true(1)), Err(overflow));
+            assert_eq!(flag_ls.try_splice(0..0, [false]).err(), Some(overflow));
+            let attempt_len = T::MAX_LENGTH + 5;
+            assert_eq!(flag_ls.try_splice(0..4, [false; 9]).err(), Some(FlagLsError::MaximumLengthExceeded { mx_len: T::MAX_LENGTH, attempt_len }));
+            assert!(flag_ls.iter().eq(before.iter()));
+            assert_eq!(flag_ls.try_splice(1..3, [false]).map(|out| out.len()), Ok(2));
+            assert_eq!(flag_ls.checked_get(1), Ok(false));
+            assert_eq!(flag_ls.len(), T::MAX_LENGTH - 1);
+        }
+        for len in [2, 31, 32] {
+            check::<B32>(len)?;
+            check::<B64>(len)?;
+            check::<B128>(len)?;
+            check::<Bsize>(len)?;
+            check::<Blong>(len)?;
+            check::<BAuto>(len)?;
+        }
+        check::<B128>(128)?;
+        check::<Blong>(200)?;
+        check_full::<B32>();
+        check_full::<B64>();
+        check_full::<B128>();
+        check_full::<Bsize>();
+        Ok(())
+    }
+    #[test]
+    fn boundaries() {
+        use std::ops::{Not, Shl, Shr};
+        fn model<T: FlagLs>(flag_ls: &T) -> Vec<bool> {
+            flag_ls.iter().collect()
+        }
+        fn check<T: FlagLs + Clone + Not<Output = T>>(len: usize) {
+            let pattern: Vec<bool> = (0..len).map(|idx| idx % 3 == 0).collect();
+            let flag_ls = T::from_iter(pattern.clone());
+            assert_eq!(model(&flag_ls), pattern);
+            assert_eq!(model(&!flag_ls.clone()), pattern.iter().map(|flag| !flag).collect::<Vec<_>>());
+            assert_eq!(model(&!T::all_true(len)), vec![false; len]);
+            assert_eq!(model(&!T::all_false(len)), vec![true; len]);
+
+            let mut resized = T::all_true(len);
+            resized.set_len(len);
+            assert_eq!(model(&resized), vec![true; len]);
+            resized.set_len(0);
+            assert!(resized.is_empty());
+            resized.set_len(len);
+            assert_eq!(model(&resized), vec![false; len]);
+            assert_eq!(T::all_true(len).try_push(true).is_err(), len == T::MAX_LENGTH);
+
+            for idx in 0..len {
+                let mut edited = flag_ls.clone();
+                let mut expected = pattern.clone();
+                assert_eq!(edited.remove(idx), expected.remove(idx));
+                assert_eq!(model(&edited), expected);
+                edited.insert(idx, pattern[idx]);
+                assert_eq!(model(&edited), pattern);
+                edited.set(idx, !pattern[idx]);
+                expected = pattern.clone();
+                expected[idx] = !expected[idx];
+                assert_eq!(model(&edited), expected);
+                edited.swap(idx, len - 1);
+                expected.swap(idx, len - 1);
+                assert_eq!(model(&edited), expected);
+            }
+
+            let width = len.min(128);
+            let field = pattern[len - width..].iter().rev().fold(0, |acc, flag| (acc << 1) | u128::from(*flag));
+            assert_eq!(flag_ls.get_bits(len - width..len), field);
+            let mut fields = T::all_false(len);
+            fields.set_bits(len - width..len, u128::MAX);
+            assert_eq!(fields.and_count(&fields), width);
+            assert_eq!(fields.or_count(&T::all_true(len)), len);
+            assert_eq!(fields.xor_count(&T::all_true(len)), len - width);
+
+            let mut edited = flag_ls.clone();
+            edited.reverse();
+            assert!(edited.iter().eq(pattern.iter().rev().copied()));
+            let mut edited = flag_ls.clone();
+            let ones = pattern.iter().filter(|flag| **flag).count();
+            assert_eq!(edited.stable_partition(), ones);
+            assert!(edited.iter().eq((0..len).map(|idx| idx < ones)));
+            let mut edited = flag_ls.clone();
+            edited.dedup();
+            let mut expected = pattern.clone();
+            expected.dedup();
+            assert_eq!(model(&edited), expected);
+            let mut edited = flag_ls.clone();
+            edited.retain(|idx, _| idx % 2 == 1);
+            assert_eq!(edited.len(), len / 2);
+            let mut edited = flag_ls;
+            for expected in pattern.iter().rev() {
+                assert_eq!(edited.pop(), Some(*expected));
+            }
+            assert_eq!(edited.pop(), None);
+        }
+        fn check_shifts<T: FlagLs + Clone + Shl<usize, Output = T> + Shr<usize, Output = T>>() {
+            let full = T::all_true(T::MAX_LENGTH);
+            for rhs in [0, 1, T::MAX_LENGTH - 1, T::MAX_LENGTH, T::MAX_LENGTH + 1, usize::MAX] {
+                let moved = rhs.min(T::MAX_LENGTH);
+                assert!((full.clone() << rhs).iter().eq((0..T::MAX_LENGTH).map(|idx| idx >= moved)));
+                assert_eq!(model(&(full.clone() >> rhs)), vec![true; T::MAX_LENGTH - moved]);
+                assert_eq!(model(&(T::default() << rhs)), vec![false; moved]);
+                assert!((T::default() >> rhs).is_empty());
+            }
+        }
+        each_backend!(check(len));
+        check_shifts::<B32>();
+        check_shifts::<B64>();
+        check_shifts::<B128>();
+        check_shifts::<Bsize>();
+    }
+    #[test]
+    fn debug_output() {
+        fn check<T: FlagLs + std::fmt::Debug>(name: &str) {
+            let flag_ls = T::from_iter([true, true, false, true, false, false, true]);
+            assert_eq!(format!("{flag_ls:?}"), format!("{name}[1101001](len=7)"));
+            assert_eq!(format!("{flag_ls:#?}"), format!("{name} {{\n    len: 7,\n    ones: 4,\n    set: [0..=1, 3, 6],\n}}"));
+            assert_eq!(format!("{:?}", T::default()), format!("{name}[](len=0)"));
+            assert_eq!(format!("{:#?}", T::default()), format!("{name} {{\n    len: 0,\n    ones: 0,\n    set: [],\n}}"));
+        }
+        check::<B32>("B32");
+        check::<B64>("B64");
+        check::<B128>("B128");
+        check::<Bsize>("Bsize");
+        check::<Blong>("Blong");
+        check::<BAuto>("BAuto");
+        assert_eq!(format!("{:?}", B32::all_true(32)), format!("B32[{}](len=32)", "1".repeat(32)));
+        assert_eq!(format!("{:?}", Blong::all_false(128)), format!("Blong[{}](len=128)", "0".repeat(128)));
+        let mut long = Blong::all_false(300);
+        for idx in [0, 63, 64, 100, 101, 102, 235, 236, 299] {
+            long.set(idx, true);
+        }
+        assert_eq!(
+            format!("{long:?}"),
+            format!("Blong[1{}1…(172 more, 5 set)…1{}1](len=300)", "0".repeat(62), "0".repeat(62))
+        );
+        assert!(format!("{long:#?}").contains("set: [0, 63..=64, 100..=102, 235..=236, 299]"));
+        let alternating = Blong::from_iter((0..200).map(|idx| idx % 2 == 0));
+        let pretty = format!("{alternating:#?}");
+        assert!(pretty.contains("set: [0, 2, 4,"));
+        assert!(pretty.contains(", 62, …(36 more runs)…, 136, "));
+        assert!(pretty.contains(", 196, 198],"));
+    }
+    #[test]
+    fn cpu_text() -> Result<(), CpuTextError> {
+        fn check<T: FlagLs + PartialEq + std::fmt::Debug>(rng: &mut SplitMix64) -> Result<(), CpuTextError> {
+            for len in [0, 1, 3, 4, 31, 32, 33, 64, 100, 128, 300].into_iter().filter(|len| *len <= T::MAX_LENGTH) {
+                for p in [0.0, 0.1, 0.5, 0.9, 1.0] {
+                    let flags = T::random(len, p, || rng.next_u64());
+                    let mut from_list = T::from_cpulist(&flags.to_cpulist())?;
+                    assert_eq!(from_list.to_cpulist(), flags.to_cpulist());
+                    from_list.set_len(len);
+                    assert_eq!(from_list, flags, "cpulist of {len} flags");
+                    let mask = flags.to_cpumask();
+                    let mut from_mask = T::from_cpumask(&mask)?;
+                    assert_eq!(from_mask.to_cpumask(), mask);
+                    assert_eq!(from_mask.len(), len.div_ceil(4) * 4);
+                    from_mask.set_len(len);
+                    assert_eq!(from_mask, flags, "cpumask of {len} flags");
+                }
+            }
+            Ok(())
+        }
+        let mut rng = SplitMix64::new(50);
+        check::<B32>(&mut rng)?;
+        check::<B64>(&mut rng)?;
+        check::<B128>(&mut rng)?;
+        check::<Bsize>(&mut rng)?;
+        check::<Blong>(&mut rng)?;
+        check::<BAuto>(&mut rng)?;
+        assert_eq!(Blong::from_cpulist(" 8,0-3,2 ")?.to_cpulist(), "0-3,8");
+        assert_eq!(Blong::from_cpulist("0-1023:2/256")?.to_cpulist(), "0-1,256-257,512-513,768-769");
+        assert_eq!(Blong::from_cpulist("0-5:4/4")?.to_cpulist(), "0-5");
+        assert_eq!(Blong::from_cpulist("")?.len(), 0);
+        assert_eq!(Blong::from_cpumask("0")?, Blong::all_false(4));
+        assert_eq!(Blong::from_cpumask("0x1,f")?.to_cpulist(), "0-3,32");
+        assert_eq!(Blong::from_cpulist("1,,2"), Err(CpuTextError::MissingNumber { pos: 2 }));
+        assert_eq!(Blong::from_cpulist("0-3,"), Err(CpuTextError::MissingNumber { pos: 4 }));
+        assert_eq!(Blong::from_cpulist("0-3:2"), Err(CpuTextError::MissingNumber { pos: 5 }));
+        assert_eq!(Blong::from_cpulist("0-3:0/2"), Err(CpuTextError::BadStride { pos: 4, used: 0, group: 2 }));
+        assert_eq!(Blong::from_cpulist("0-3:3/x"), Err(CpuTextError::UnexpectedChar { pos: 6, found: 'x' }));
+        assert_eq!(Blong::from_cpulist("1, 2"), Err(CpuTextError::UnexpectedChar { pos: 2, found: ' ' }));
+        assert_eq!(Blong::from_cpulist("99999999999999999999999"), Err(CpuTextError::NumberTooLarge { pos: 0 }));
+        assert_eq!(B64::from_cpulist("0,63-64"), Err(CpuTextError::IndexTooLarge { pos: 2, idx: 64, mx_len: 64 }));
+        assert_eq!(B32::from_cpumask(" 123456789"), Err(CpuTextError::GroupTooLong { pos: 1, digits: 9 }));
+        assert_eq!(B32::from_cpumask("f,,f"), Err(CpuTextError::MissingNumber { pos: 2 }));
+        assert_eq!(B32::from_cpumask("0,0000000f")?, B32::from_iter((0..32).map(|idx| idx < 4)));
+        assert_eq!(Blong::from_cpulist("0-3,x").map_err(|err| err.to_string()), Err("unexpected character 'x' at position 4".to_owned()));
+        Ok(())
+    }
+    #[test]
+    fn blong_word_shifts() {
+        for len in [0, 1, 63, 64, 65, 127, 128, 129, 200] {
+            let model: Vec<bool> = (0..len).map(|idx| idx % 3 != 1).collect();
+            let flag_ls = Blong::from_iter(model.clone());
+            for index in 0..=len {
+                for count in [0, 1, 63, 64, 65, 130] {
+                    let mut inserted = flag_ls.clone();
+                    inserted.insert_many(index, count, true);
+                    let mut expected = model.clone();
+                    expected.splice(index..index, vec![true; count]);
+                    assert_eq!(inserted, Blong::from_iter(expected.clone()), "insert {count} at {index} of {len}");
+                    inserted.remove_range(index..index + count);
+                    assert_eq!(inserted, flag_ls, "remove {count} at {index} of {len}");
+                }
+                if index < len {
+                    let mut removed = flag_ls.clone();
+                    assert_eq!(removed.remove(index), model[index]);
+                    removed.insert(index, model[index]);
+                    assert_eq!(removed, flag_ls);
+                    assert_eq!(flag_ls[index], model[index]);
+                }
+            }
+        }
+        assert!(std::panic::catch_unwind(|| Blong::all_true(10)[10]).is_err());
+    }
+    /// Random operations on a `Blong`, each checked against the same operation on a `Vec<bool>`
+    mod blong_differential {
+        use super::*;
+        fn pick(rng: &mut SplitMix64, bound: usize) -> usize {
+            usize::try_from(rng.next_u64() % u64::try_from(bound).expect("small")).expect("small")
+        }
+        fn random_model(rng: &mut SplitMix64, max_len: usize) -> Vec<bool> {
+            (0..pick(rng, max_len)).map(|_| rng.next_u64() & 1 == 1).collect()
+        }
+        fn padded(model: &[bool], idx: usize) -> bool {
+            model.get(idx).copied().unwrap_or(false)
+        }
+        fn assert_same(flag_ls: &Blong, model: &[bool]) {
+            assert!(flag_ls.iter().eq(model.iter().copied()), "{flag_ls:?} != {model:?}");
+            assert!(flag_ls.iter().rev().eq(model.iter().rev().copied()));
+            assert_eq!(flag_ls.inner().len(), model.len().div_ceil(usize::BITS as usize));
+            let rem = model.len() % usize::BITS as usize;
+            if let (Some(last), true) = (flag_ls.inner().last(), rem > 0) {
+                assert_eq!(last >> rem, 0, "flags past the end must stay cleared");
+            }
+        }
+        fn edit(rng: &mut SplitMix64, flag_ls: &mut Blong, model: &mut Vec<bool>) {
+            let len = model.len();
+            let idx = pick(rng, len + 1);
+            let flag = rng.next_u64() & 1 == 1;
+            match pick(rng, 9) {
+                0 => {
+                    flag_ls.push(flag);
+                    model.push(flag);
+                }
+                7 => {
+                    let count = pick(rng, 200);
+                    flag_ls.insert_many(idx, count, flag);
+                    model.splice(idx..idx, vec![flag; count]);
+                }
+                8 => {
+                    let end = idx + pick(rng, len - idx + 1);
+                    flag_ls.remove_range(idx..end);
+                    model.drain(idx..end);
+                }
+                1 => assert_eq!(flag_ls.pop(), model.pop()),
+                2 => {
+                    flag_ls.insert(idx, flag);
+                    model.insert(idx, flag);
+                }
+                3 if idx < len => assert_eq!(flag_ls.remove(idx), model.remove(idx)),
+                4 if idx < len => {
+                    flag_ls.set(idx, flag);
+                    model[idx] = flag;
+                    assert_eq!(flag_ls[idx], flag);
+                    assert_eq!(flag_ls.get(idx), Some(flag));
+                }
+                5 => {
+                    let new_len = pick(rng, 300);
+                    flag_ls.set_len(new_len);
+                    model.resize(new_len, false);
+                }
+                _ => {
+                    flag_ls.truncate(idx);
+                    model.truncate(idx);
+                }
+            }
+        }
+        fn bitwise(rng: &mut SplitMix64, flag_ls: &mut Blong, model: &mut Vec<bool>) {
+            let other_model = random_model(rng, 300);
+            let other = Blong::from_iter(other_model.clone());
+            let longest = model.len().max(other_model.len());
+            let combined = |op: fn(bool, bool) -> bool| (0..longest).map(|idx| op(padded(model, idx), padded(&other_model, idx))).collect::<Vec<_>>();
+            let count = |op: fn(bool, bool) -> bool| combined(op).iter().filter(|flag| **flag).count();
+            assert_eq!(flag_ls.and_count(&other), count(|l, r| l & r));
+            assert_eq!(flag_ls.or_count(&other), count(|l, r| l | r));
+            assert_eq!(flag_ls.xor_count(&other), count(|l, r| l ^ r));
+            assert_eq!(*flag_ls == other, *model == other_model);
+            let result = match pick(rng, 5) {
+                0 => {
+                    *flag_ls &= &other;
+                    combined(|l, r| l & r)
+                }
+                1 => {
+                    *flag_ls |= &other;
+                    combined(|l, r| l | r)
+                }
+                2 => {
+                    *flag_ls ^= &other;
+                    combined(|l, r| l ^ r)
+                }
+                3 => {
+                    *flag_ls -= &other;
+                    model.iter().enumerate().map(|(idx, flag)| *flag && !padded(&other_model, idx)).collect()
+                }
+                _ => {
+                    *flag_ls = !std::mem::take(flag_ls);
+                    model.iter().map(|flag| !flag).collect()
+                }
+            };
+            *model = result;
+        }
+        fn reorder(rng: &mut SplitMix64, flag_ls: &mut Blong, model: &mut Vec<bool>) {
+            let len = model.len();
+            match pick(rng, 5) {
+                0 => {
+                    flag_ls.reverse();
+                    model.reverse();
+                }
+                1 => {
+                    let modulus = pick(rng, 5) + 1;
+                    flag_ls.retain(|idx, flag| flag || idx % modulus == 0);
+                    let mut idx = 0;
+                    model.retain(|flag| {
+                        idx += 1;
+                        *flag || (idx - 1) % modulus == 0
+                    });
+                }
+                2 => {
+                    flag_ls.dedup();
+                    model.dedup();
+                }
+                3 => {
+                    let ones = model.iter().filter(|flag| **flag).count();
+                    assert_eq!(flag_ls.stable_partition(), ones);
+                    *model = (0..len).map(|idx| idx < ones).collect();
+                }
+                _ if len > 0 => {
+                    let (first, second) = (pick(rng, len), pick(rng, len));
+                    flag_ls.swap(first, second);
+                    model.swap(first, second);
+                }
+                _ => {}
+            }
+        }
+        fn splice(rng: &mut SplitMix64, flag_ls: &mut Blong, model: &mut Vec<bool>) {
+            let len = model.len();
+            let idx = pick(rng, len + 1);
+            match pick(rng, 5) {
+                0 => {
+                    let tail = flag_ls.split_off(idx);
+                    assert!(tail.iter().eq(model.split_off(idx)));
+                }
+                1 => {
+                    let end = idx + pick(rng, len - idx + 1);
+                    let other_model = random_model(rng, 100);
+                    let removed = flag_ls.splice(idx..end, other_model.clone());
+                    assert!(removed.iter().eq(model.splice(idx..end, other_model)));
+                }
+                2 => {
+                    let end = idx + pick(rng, (len - idx).min(128) + 1);
+                    let field = model[idx..end].iter().rev().fold(0, |acc, flag| (acc << 1) | u128::from(*flag));
+                    assert_eq!(flag_ls.get_bits(idx..end), field);
+                    let value = u128::from(rng.next_u64()) << 64 | u128::from(rng.next_u64());
+                    flag_ls.set_bits(idx..end, value);
+                    for (offset, flag) in model[idx..end].iter_mut().enumerate() {
+                        *flag = (value >> offset) & 1 == 1;
+                    }
+                }
+                3 => {
+                    let mut other_model = random_model(rng, 100);
+                    flag_ls.append(&mut Blong::from_iter(other_model.clone()));
+                    model.append(&mut other_model);
+                }
+                _ => {
+                    flag_ls.clear();
+                    model.clear();
+                }
+            }
+        }
+        #[test]
+        fn matches_vec() {
+            let mut rng = SplitMix64::new(47);
+            let mut flag_ls = Blong::default();
+            let mut model = Vec::new();
+            for _ in 0..4000 {
+                match pick(&mut rng, 8) {
+                    0..=2 => edit(&mut rng, &mut flag_ls, &mut model),
+                    3 | 4 => bitwise(&mut rng, &mut flag_ls, &mut model),
+                    5 | 6 => reorder(&mut rng, &mut flag_ls, &mut model),
+                    _ => splice(&mut rng, &mut flag_ls, &mut model),
+                }
+                assert_same(&flag_ls, &model);
+            }
+        }
+    }
+    #[test]
+    fn packed_ints() -> Result<(), FlagLsError> {
+        fn check<const BITS: usize>() -> Result<(), FlagLsError> {
+            let values: Vec<usize> = (0..200).map(|i: usize| i.wrapping_mul(0x9E37_79B9) & PackedInts::<BITS>::MAX_VALUE).collect();
+            let mut ls: PackedInts<BITS> = values.iter().copied().collect();
+            let mut expected = values;
+            assert_eq!(ls.len(), expected.len());
+            assert!(ls.iter().eq(expected.iter().copied()));
+            assert!(ls.iter().rev().eq(expected.iter().rev().copied()));
+            ls.insert(37, PackedInts::<BITS>::MAX_VALUE);
+            expected.insert(37, PackedInts::<BITS>::MAX_VALUE);
+            assert_eq!(ls.remove(3), expected.remove(3));
+            assert_eq!(ls.try_set(100, 1), Some(expected[100]));
+            expected[100] = 1;
+            assert!(ls.iter().eq(expected.iter().copied()));
+            assert_eq!(ls.try_insert(500, 0), Err(FlagLsError::IndexOutOfBounds { idx: 500, len: expected.len() }));
+            ls.try_push(0)?;
+            assert_eq!(ls.pop(), Some(0));
+            ls.truncate(10);
+            expected.truncate(10);
+            assert_eq!(ls, expected.iter().copied().collect());
+            assert_eq!(ls.try_remove(10), None);
+            ls.set_len(12);
+            assert_eq!(ls.get(11), Some(0));
+            ls.clear();
+            assert!(ls.is_empty());
+            Ok(())
+        }
+        check::<1>()?;
+        check::<3>()?;
+        check::<7>()?;
+        check::<13>()?;
+        check::<32>()?;
+        check::<63>()?;
+        check::<64>()
+    }
+    #[test]
     fn is_empty() {
         let l1 = B32::default();
         let l2 = B32::all_true(1);
@@ -0,0 +1,226 @@
+use crate::{Blong, FlagLs, B128, B32, B64};
+
+/// Spreads the low 32 bits of `x` out to the even bits of a `u64`
+const fn spread_u64(x: u64) -> u64 {
+    let mut x = x & 0xFFFF_FFFF;
+    x = (x | (x << 16)) & (u64::MAX / 0x1_0001);
+    x = (x | (x << 8)) & (u64::MAX / 0x101);
+    x = (x | (x << 4)) & (u64::MAX / 0x11);
+    x = (x | (x << 2)) & (u64::MAX / 0x5);
+    (x | (x << 1)) & (u64::MAX / 0x3)
+}
+/// Gathers the even bits of `x` into the low 32 bits, the inverse of [`spread_u64`]
+const fn compact_u64(x: u64) -> u64 {
+    let mut x = x & (u64::MAX / 0x3);
+    x = (x | (x >> 1)) & (u64::MAX / 0x5);
+    x = (x | (x >> 2)) & (u64::MAX / 0x11);
+    x = (x | (x >> 4)) & (u64::MAX / 0x101);
+    x = (x | (x >> 8)) & (u64::MAX / 0x1_0001);
+    (x | (x >> 16)) & 0xFFFF_FFFF
+}
+/// Spreads the low 64 bits of `x` out to the even bits of a `u128`
+const fn spread_u128(x: u128) -> u128 {
+    let mut x = x & 0xFFFF_FFFF_FFFF_FFFF;
+    x = (x | (x << 32)) & (u128::MAX / 0x1_0000_0001);
+    x = (x | (x << 16)) & (u128::MAX / 0x1_0001);
+    x = (x | (x << 8)) & (u128::MAX / 0x101);
+    x = (x | (x << 4)) & (u128::MAX / 0x11);
+    x = (x | (x << 2)) & (u128::MAX / 0x5);
+    (x | (x << 1)) & (u128::MAX / 0x3)
+}
+/// Gathers the even bits of `x` into the low 64 bits, the inverse of [`spread_u128`]
+const fn compact_u128(x: u128) -> u128 {
+    let mut x = x & (u128::MAX / 0x3);
+    x = (x | (x >> 1)) & (u128::MAX / 0x5);
+    x = (x | (x >> 2)) & (u128::MAX / 0x11);
+    x = (x | (x >> 4)) & (u128::MAX / 0x101);
+    x = (x | (x >> 8)) & (u128::MAX / 0x1_0001);
+    x = (x | (x >> 16)) & (u128::MAX / 0x1_0000_0001);
+    (x | (x >> 32)) & 0xFFFF_FFFF_FFFF_FFFF
+}
+/// Spreads the low 21 bits of `x` out to every third bit of a `u64`
+const fn spread_3d(x: u64) -> u64 {
+    let mut x = x & 0x1F_FFFF;
+    x = (x | (x << 32)) & 0x001F_0000_0000_FFFF;
+    x = (x | (x << 16)) & 0x001F_0000_FF00_00FF;
+    x = (x | (x << 8)) & 0x100F_00F0_0F00_F00F;
+    x = (x | (x << 4)) & 0x10C3_0C30_C30C_30C3;
+    (x | (x << 2)) & 0x1249_2492_4924_9249
+}
+/// Gathers every third bit of `x` into the low 21 bits, the inverse of [`spread_3d`]
+const fn compact_3d(x: u64) -> u64 {
+    let mut x = x & 0x1249_2492_4924_9249;
+    x = (x | (x >> 2)) & 0x10C3_0C30_C30C_30C3;
+    x = (x | (x >> 4)) & 0x100F_00F0_0F00_F00F;
+    x = (x | (x >> 8)) & 0x001F_0000_FF00_00FF;
+    x = (x | (x >> 16)) & 0x001F_0000_0000_FFFF;
+    (x | (x >> 32)) & 0x1F_FFFF
+}
+/// Interleaves the bits of two coordinates into a Morton (Z-order) code, with `x` in the even bits and `y` in the odd bits
+/// # Examples
+/// ```
+/// use packed_flags::{morton_decode_2d, morton_encode_2d};
+///
+/// assert_eq!(morton_encode_2d(0b11,0b00),0b0101);
+/// assert_eq!(morton_encode_2d(0b00,0b11),0b1010);
+/// assert_eq!(morton_decode_2d(morton_encode_2d(12345,67890)),(12345,67890));
+/// ```
+#[must_use]
+pub const fn morton_encode_2d(x: u32, y: u32) -> u64 {
+    spread_u64(x as u64) | (spread_u64(y as u64) << 1)
+}
+/// Splits a Morton (Z-order) code back into the two coordinates given to [`morton_encode_2d`]
+#[must_use]
+#[allow(clippy::cast_possible_truncation)]
+pub const fn morton_decode_2d(code: u64) -> (u32, u32) {
+    (compact_u64(code) as u32, compact_u64(code >> 1) as u32)
+}
+/// Interleaves the bits of three coordinates into a Morton (Z-order) code, with `x` in bits `0,3,6..`, `y` in bits `1,4,7..` and `z` in bits `2,5,8..`
+///
+/// Only the low 21 bits of each coordinate fit in the code, any higher bits are ignored
+/// # Examples
+/// ```
+/// use packed_flags::{morton_decode_3d, morton_encode_3d};
+///
+/// assert_eq!(morton_encode_3d(1,1,1),0b111);
+/// assert_eq!(morton_encode_3d(0b10,0,0),0b1000);
+/// assert_eq!(morton_decode_3d(morton_encode_3d(1000,2000,3000)),(1000,2000,3000));
+/// ```
+#[must_use]
+pub const fn morton_encode_3d(x: u32, y: u32, z: u32) -> u64 {
+    spread_3d(x as u64) | (spread_3d(y as u64) << 1) | (spread_3d(z as u64) << 2)
+}
+/// Splits a Morton (Z-order) code back into the three coordinates given to [`morton_encode_3d`]
+#[must_use]
+#[allow(clippy::cast_possible_truncation)]
+pub const fn morton_decode_3d(code: u64) -> (u32, u32, u32) {
+    (compact_3d(code) as u32, compact_3d(code >> 1) as u32, compact_3d(code >> 2) as u32)
+}
+impl B64 {
+    /// Interleaves two lists of flags, so flag `2i` is `a[i]` and flag `2i+1` is `b[i]`
+    ///
+    /// The result has twice the length of the longer list, with the shorter list padded with false
+    /// # Examples
+    /// ```
+    /// use packed_flags::{B32, B64};
+    /// use packed_flags::FlagLs;
+    ///
+    /// let a=B32::from_iter(vec![true,true,false]);
+    /// let b=B32::from_iter(vec![false,true,true]);
+    /// let mixed=B64::interleave(a,b);
+    /// assert_eq!(mixed,B64::from_iter(vec![true,false,true,true,false,true]));
+    /// assert_eq!(mixed.deinterleave(),(a,b));
+    /// ```
+    #[must_use]
+    pub fn interleave(a: B32, b: B32) -> Self {
+        let inner = spread_u64(u64::from(a.as_inner())) | (spread_u64(u64::from(b.as_inner())) << 1);
+        Self::initialize(inner, 2 * a.len().max(b.len()))
+    }
+    /// Splits the even and odd flags into two lists, the inverse of [`interleave`][B64::interleave]
+    ///
+    /// If the length is odd the list of even flags is one longer
+    /// # Panics
+    /// Never, the halves always fit in a `B32`
+    #[must_use]
+    pub fn deinterleave(self) -> (B32, B32) {
+        let inner = self.as_inner();
+        let even = u32::try_from(compact_u64(inner)).expect("Infalible");
+        let odd = u32::try_from(compact_u64(inner >> 1)).expect("Infalible");
+        (B32::initialize(even, self.len().div_ceil(2)), B32::initialize(odd, self.len() / 2))
+    }
+}
+impl B128 {
+    /// Interleaves two lists of flags, so flag `2i` is `a[i]` and flag `2i+1` is `b[i]`
+    ///
+    /// The result has twice the length of the longer list, with the shorter list padded with false
+    /// # Examples
+    /// ```
+    /// use packed_flags::{B64, B128};
+    /// use packed_flags::FlagLs;
+    ///
+    /// let a=B64::all_true(64);
+    /// let b=B64::all_false(64);
+    /// let mixed=B128::interleave(a,b);
+    /// assert_eq!(mixed.as_inner(),u128::MAX/3);
+    /// assert_eq!(mixed.deinterleave(),(a,b));
+    /// ```
+    #[must_use]
+    pub fn interleave(a: B64, b: B64) -> Self {
+        let inner = spread_u128(u128::from(a.as_inner())) | (spread_u128(u128::from(b.as_inner())) << 1);
+        Self::initialize(inner, 2 * a.len().max(b.len()))
+    }
+    /// Splits the even and odd flags into two lists, the inverse of [`interleave`][B128::interleave]
+    ///
+    /// If the length is odd the list of even flags is one longer
+    /// # Panics
+    /// Never, the halves always fit in a `B64`
+    #[must_use]
+    pub fn deinterleave(self) -> (B64, B64) {
+        let inner = self.as_inner();
+        let even = u64::try_from(compact_u128(inner)).expect("Infalible");
+        let odd = u64::try_from(compact_u128(inner >> 1)).expect("Infalible");
+        (B64::initialize(even, self.len().div_ceil(2)), B64::initialize(odd, self.len() / 2))
+    }
+}
+impl Blong {
+    /// Interleaves any number of lists of flags, so flag `i*n+k` is flag `i` of list `k`, where there are `n` lists
+    ///
+    /// The result has `n` times the length of the longest list, with shorter lists padded with false
+    /// # Panics
+    /// If the result would be longer than `usize::MAX` flags
+    /// # Examples
+    /// ```
+    /// use packed_flags::Blong;
+    /// use packed_flags::FlagLs;
+    ///
+    /// let lists=vec![Blong::all_true(2),Blong::all_false(2),Blong::from_iter(vec![false,true])];
+    /// let mixed=Blong::interleave(&lists);
+    /// assert_eq!(mixed,Blong::from_iter(vec![true,false,false,true,false,true]));
+    /// assert_eq!(mixed.deinterleave(3),lists);
+    /// ```
+    #[must_use]
+    pub fn interleave(lists: &[Self]) -> Self {
+        let ways = lists.len();
+        let mut out = Self::default();
+        let len = ways.checked_mul(lists.iter().map(FlagLs::len).max().unwrap_or(0)).expect("Cannot interleave more than the maximum length of a Blong");
+        out.set_len(len);
+        if let [a, b] = lists {
+            // two lists are spread 64 flags at a time, the same way as a Morton code
+            let half = |list: &Self, start: usize| list.get_bits(start.min(list.len())..list.len().min(start + 64));
+            for start in (0..len / 2).step_by(64) {
+                out.set_bits(2 * start..len.min(2 * start + 128), spread_u128(half(a, start)) | (spread_u128(half(b, start)) << 1));
+            }
+        } else {
+            for (way, list) in lists.iter().enumerate() {
+                for (idx, flag) in list.iter().enumerate() {
+                    if flag {
+                        out.set(idx * ways + way, true);
+                    }
+                }
+            }
+        }
+        out
+    }
+    /// Splits the flags into `ways` lists, so list `k` holds flags `k`, `k+ways`, `k+2*ways` and so on, the inverse of [`interleave`][Blong::interleave]
+    ///
+    /// If the length is not a multiple of `ways` the first lists are one longer than the others
+    /// # Panics
+    /// If `ways` is zero
+    #[must_use]
+    pub fn deinterleave(&self, ways: usize) -> Vec<Self> {
+        assert!(ways > 0, "Cannot split flags into zero lists");
+        if ways == 2 {
+            let (mut even, mut odd) = (Self::default(), Self::default());
+            even.set_len(self.len().div_ceil(2));
+            odd.set_len(self.len() / 2);
+            for start in (0..self.len()).step_by(128) {
+                let pair = self.get_bits(start..self.len().min(start + 128));
+                let half = start / 2;
+                even.set_bits(half..even.len().min(half + 64), compact_u128(pair));
+                odd.set_bits(half..odd.len().min(half + 64), compact_u128(pair >> 1));
+            }
+            return vec![even, odd];
+        }
+        (0..ways).map(|way| Self::from_iter(self.iter().skip(way).step_by(ways))).collect()
+    }
+}
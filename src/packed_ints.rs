@@ -0,0 +1,231 @@
+use crate::FlagLsError;
+/// A list of small unsigned integers, each stored in exactly `BITS` bits
+///
+/// This is the multi-bit version of [`Blong`][crate::Blong], and has the same shape of api as [`FlagLs`][crate::FlagLs].
+/// Elements are packed back to back, so an element may straddle two words
+/// # Examples
+/// ```
+/// use packed_flags::PackedInts;
+///
+/// let mut ls: PackedInts<3>=PackedInts::new();
+/// ls.push(5);
+/// ls.push(7);
+/// ls.insert(1,2);
+/// assert_eq!(ls.iter().collect::<Vec<_>>(),vec![5,2,7]);
+/// assert_eq!(ls.remove(0),5);
+/// assert_eq!(ls.get(1),Some(7));
+/// ```
+#[derive(PartialEq, Eq, Default, Clone, Debug, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PackedInts<const BITS: usize> {
+    inner: Vec<usize>,
+    len: usize,
+}
+impl<const BITS: usize> PackedInts<BITS> {
+    const INNER_SIZE: usize = usize::BITS as usize;
+    const VALID_BITS: () = assert!(BITS > 0 && BITS <= usize::BITS as usize, "PackedInts elements must be between 1 and usize::BITS bits wide");
+    /// The largest value an element can hold
+    pub const MAX_VALUE: usize = usize::MAX >> (usize::BITS as usize - BITS);
+    /// The max number of elements a list can store
+    pub const MAX_LENGTH: usize = usize::MAX / BITS;
+    #[must_use]
+    /// Creates an empty list
+    pub const fn new() -> Self {
+        let () = Self::VALID_BITS;
+        Self { inner: Vec::new(), len: 0 }
+    }
+    fn check_value(value: usize) {
+        assert!(value <= Self::MAX_VALUE, "Cannot store {value} in {BITS} bits");
+    }
+    /// Read the element starting at bit `pos`, which may straddle two words
+    fn read_raw(&self, pos: usize) -> usize {
+        let (t_pos, m_pos) = (pos / Self::INNER_SIZE, pos % Self::INNER_SIZE);
+        let mut out = self.inner[t_pos] >> m_pos;
+        if m_pos + BITS > Self::INNER_SIZE {
+            out |= self.inner[t_pos + 1] << (Self::INNER_SIZE - m_pos);
+        }
+        out & Self::MAX_VALUE
+    }
+    /// Write the element starting at bit `pos`, which may straddle two words
+    fn write_raw(&mut self, pos: usize, value: usize) {
+        let (t_pos, m_pos) = (pos / Self::INNER_SIZE, pos % Self::INNER_SIZE);
+        self.inner[t_pos] = (self.inner[t_pos] & !(Self::MAX_VALUE << m_pos)) | (value << m_pos);
+        if m_pos + BITS > Self::INNER_SIZE {
+            let shift = Self::INNER_SIZE - m_pos;
+            self.inner[t_pos + 1] = (self.inner[t_pos + 1] & !(Self::MAX_VALUE >> shift)) | (value >> shift);
+        }
+    }
+    /// Returns the number of elements in the list
+    #[must_use]
+    pub const fn len(&self) -> usize {
+        self.len
+    }
+    /// Returns true when there are no elements in the list
+    #[must_use]
+    pub const fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+    /// Sets the length of the list, filling any new elements with 0
+    /// # Panics
+    /// Panics if the new length would be larger than `MAX_LENGTH`
+    pub fn set_len(&mut self, new_len: usize) {
+        assert!(new_len <= Self::MAX_LENGTH, "Cannot set length to a length larger than {}", Self::MAX_LENGTH);
+        let bits = new_len * BITS;
+        self.inner.resize(bits.div_ceil(Self::INNER_SIZE), 0);
+        let rem = bits % Self::INNER_SIZE;
+        if let (Some(last), true) = (self.inner.last_mut(), rem > 0) {
+            *last &= (1 << rem) - 1;
+        }
+        self.len = new_len;
+    }
+    /// Truncate the list to `len` elements. Does nothing if `len>=self.len()`
+    pub fn truncate(&mut self, len: usize) {
+        if len < self.len {
+            self.set_len(len);
+        }
+    }
+    /// Clears the list, setting its length to 0
+    pub fn clear(&mut self) {
+        self.inner.clear();
+        self.len = 0;
+    }
+    /// Returns the element at a specified index, if it exists, otherwise returns None
+    #[must_use]
+    pub fn get(&self, index: usize) -> Option<usize> {
+        (index < self.len).then(|| self.read_raw(index * BITS))
+    }
+    /// Set the element at a specified index
+    /// # Panics
+    /// If the index is out of bounds or the value does not fit in `BITS` bits
+    pub fn set(&mut self, index: usize, value: usize) {
+        assert!(index < self.len, "Cannot set element {index} of a list of length {}", self.len);
+        Self::check_value(value);
+        self.write_raw(index * BITS, value);
+    }
+    /// Attempts to set the element at the specified index, returning the element that was there if the index is in bounds
+    /// # Panics
+    /// If the value does not fit in `BITS` bits
+    pub fn try_set(&mut self, index: usize, value: usize) -> Option<usize> {
+        let old = self.get(index)?;
+        self.set(index, value);
+        Some(old)
+    }
+    /// Inserts a new element at the position given by index(ie so `ls.get(index)==Some(value)`)
+    /// # Panics
+    /// Panics if the index is out of bounds, the value does not fit in `BITS` bits, or the list is already `MAX_LENGTH` long
+    pub fn insert(&mut self, index: usize, value: usize) {
+        assert!(index <= self.len, "Cannot insert at {index} in a list of length {}", self.len);
+        Self::check_value(value);
+        self.set_len(self.len + 1);
+        for idx in (index..self.len - 1).rev() {
+            let moved = self.read_raw(idx * BITS);
+            self.write_raw((idx + 1) * BITS, moved);
+        }
+        self.write_raw(index * BITS, value);
+    }
+    /// Attempts to insert at index, returning Ok(()) on success
+    /// # Errors
+    /// Errors if the index is out of bounds(note `idx=self.len()` is considered in bounds for insert) or if the insert would bring the list above it's maximum allowed length
+    /// # Panics
+    /// If the value does not fit in `BITS` bits
+    pub fn try_insert(&mut self, index: usize, value: usize) -> Result<(), FlagLsError> {
+        if index > self.len {
+            Err(FlagLsError::IndexOutOfBounds { idx: index, len: self.len })
+        } else if self.len >= Self::MAX_LENGTH {
+            Err(FlagLsError::MaximumLengthExceeded { mx_len: Self::MAX_LENGTH, attempt_len: self.len + 1 })
+        } else {
+            self.insert(index, value);
+            Ok(())
+        }
+    }
+    /// Removes the element at the position given by index, and returns it
+    /// # Panics
+    /// Panics if the index is out of bounds
+    pub fn remove(&mut self, index: usize) -> usize {
+        assert!(index < self.len, "Cannot remove element {index} of a list of length {}", self.len);
+        let out = self.read_raw(index * BITS);
+        for idx in index + 1..self.len {
+            let moved = self.read_raw(idx * BITS);
+            self.write_raw((idx - 1) * BITS, moved);
+        }
+        self.set_len(self.len - 1);
+        out
+    }
+    /// Removes and returns the element at index, if it exists
+    pub fn try_remove(&mut self, index: usize) -> Option<usize> {
+        (index < self.len).then(|| self.remove(index))
+    }
+    /// Pushes a new element to the end of the list
+    /// # Panics
+    /// Panics if the value does not fit in `BITS` bits or the list is already `MAX_LENGTH` long
+    pub fn push(&mut self, value: usize) {
+        self.insert(self.len, value);
+    }
+    /// Attempts to push a new element to the end of the list
+    /// # Errors
+    /// Errors if this would make the list larger than `MAX_LENGTH`
+    /// # Panics
+    /// If the value does not fit in `BITS` bits
+    pub fn try_push(&mut self, value: usize) -> Result<(), FlagLsError> {
+        self.try_insert(self.len, value)
+    }
+    /// Removes and returns the last element, or none if the list is empty
+    pub fn pop(&mut self) -> Option<usize> {
+        self.len.checked_sub(1).map(|idx| self.remove(idx))
+    }
+    /// Get an iterator over all elements in the list
+    #[must_use]
+    pub const fn iter(&self) -> Iter<'_, BITS> {
+        Iter { inner: self, front: 0, back: self.len }
+    }
+}
+impl<const BITS: usize> FromIterator<usize> for PackedInts<BITS> {
+    /// # Panics
+    /// Panics if a value does not fit in `BITS` bits
+    fn from_iter<I: IntoIterator<Item = usize>>(iter: I) -> Self {
+        let mut out = Self::new();
+        for value in iter {
+            out.push(value);
+        }
+        out
+    }
+}
+impl<'a, const BITS: usize> IntoIterator for &'a PackedInts<BITS> {
+    type Item = usize;
+    type IntoIter = Iter<'a, BITS>;
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+/// An Iterator over the elements of a [`PackedInts`]
+pub struct Iter<'a, const BITS: usize> {
+    inner: &'a PackedInts<BITS>,
+    front: usize,
+    back: usize,
+}
+impl<const BITS: usize> Iterator for Iter<'_, BITS> {
+    type Item = usize;
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.front >= self.back {
+            None
+        } else {
+            self.front += 1;
+            self.inner.get(self.front - 1)
+        }
+    }
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let rem = self.back - self.front;
+        (rem, Some(rem))
+    }
+}
+impl<const BITS: usize> DoubleEndedIterator for Iter<'_, BITS> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.front >= self.back {
+            None
+        } else {
+            self.back -= 1;
+            self.inner.get(self.back)
+        }
+    }
+}
+impl<const BITS: usize> ExactSizeIterator for Iter<'_, BITS> {}
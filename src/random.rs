@@ -0,0 +1,54 @@
+/// A small, fast, seedable source of random words, using the `SplitMix64` algorithm
+///
+/// It is not cryptographically secure, but is good enough for tests and simulations.
+/// Any seed is valid, and the same seed always gives the same sequence
+/// # Examples
+/// ```
+/// use packed_flags::{SplitMix64, B64};
+/// use packed_flags::FlagLs;
+///
+/// let mut rng=SplitMix64::new(42);
+/// let flags=B64::random(64,0.25,|| rng.next_u64());
+/// assert_eq!(flags.len(),64);
+///
+/// let mut rng2=SplitMix64::new(42);
+/// assert_eq!(B64::random(64,0.25,|| rng2.next_u64()),flags);
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Default)]
+pub struct SplitMix64 {
+    state: u64,
+}
+impl SplitMix64 {
+    /// Create a generator from a seed
+    #[must_use]
+    pub const fn new(seed: u64) -> Self {
+        Self { state: seed }
+    }
+    /// Returns the next random word
+    pub const fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        mix(self.state)
+    }
+}
+/// The `SplitMix64` finaliser, which spreads every input bit over the whole output word
+pub const fn mix(mut z: u64) -> u64 {
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^ (z >> 31)
+}
+/// A uniformly random number in `0..bound`, using Lemire's multiply and reject method so there is no bias
+pub fn below(rng: &mut impl FnMut() -> u64, bound: usize) -> usize {
+    let bound = u64::try_from(bound).expect("Infalible");
+    let threshold = bound.wrapping_neg() % bound;
+    loop {
+        let product = u128::from(rng()) * u128::from(bound);
+        if u64::try_from(product & u128::from(u64::MAX)).expect("Infalible") >= threshold {
+            return usize::try_from(product >> 64).expect("Infalible");
+        }
+    }
+}
+/// A uniformly random number in `0.0..1.0`, from the top 53 bits of a word
+#[allow(clippy::cast_precision_loss)]
+pub fn unit(rng: &mut impl FnMut() -> u64) -> f64 {
+    (rng() >> 11) as f64 / (1_u64 << 53) as f64
+}